@@ -1,47 +1,40 @@
 use crate::maze::{CellKind, Coord, Maze, Rendering};
-use crate::plain::draw_cell_plain;
+use crate::plain::{draw_cell_plain, draw_cell_plain_gif};
 use image::{Rgb, RgbImage};
 use rand::distr::{Distribution, Uniform};
 
-const TILE_SIZE: u32 = 7;
+const SPRITE_SIZE: u32 = 7;
+
+/// Default size in pixels of the tiles.
+pub const TILE_SIZE: usize = SPRITE_SIZE as usize;
 
 pub struct RendererInvaders {
     pub invader_color: Rgb<u8>,
     pub wall_color: Rgb<u8>,
+    pub tile_size: usize,
 }
 
 impl RendererInvaders {
-    fn draw_invader(&self, img: &mut RgbImage, c: &Coord) {
-        let mut rng = rand::rng();
+    /* Call `put` with the position of every pixel of the tile of the invader
+     * in `c` and whether it is lit. The invader is picked from the random
+     * number generator of the cell, so that it does not change between the
+     * frames of an animation. */
+    fn invader_pixels<F: FnMut(u32, u32, bool)>(&self, maze: &Maze, c: &Coord, mut put: F) {
         let invader_range: Uniform<u16> =
             Uniform::new(0, u16::MAX).expect("cannot create uniform random distribution");
-        let invader_nbr = invader_range.sample(&mut rng);
+        let invader_nbr = invader_range.sample(&mut maze.cell_rng(c));
 
-        /* draw background */
-        for i in 0..TILE_SIZE {
-            for j in 0..TILE_SIZE {
-                img.put_pixel(
-                    c.x as u32 * TILE_SIZE + i,
-                    c.y as u32 * TILE_SIZE + j,
-                    self.wall_color,
-                );
-            }
-        }
-
-        for i in 1..4 {
-            for j in 1..6 {
-                if invader_nbr & ((1 << (i * j)) as u16) > 0 {
-                    img.put_pixel(
-                        c.x as u32 * TILE_SIZE + i,
-                        c.y as u32 * TILE_SIZE + j,
-                        self.invader_color,
-                    );
-                    img.put_pixel(
-                        c.x as u32 * TILE_SIZE + (6 - i),
-                        c.y as u32 * TILE_SIZE + j,
-                        self.invader_color,
-                    );
-                }
+        /* Sprites are designed on a 7x7 grid, symmetric around the middle
+         * column, and scaled to the tile size */
+        let size = self.tile_size as u32;
+        for i in 0..size {
+            for j in 0..size {
+                let (si, sj) = (i * SPRITE_SIZE / size, j * SPRITE_SIZE / size);
+                let si = si.min(SPRITE_SIZE - 1 - si);
+                let lit = (1..4).contains(&si)
+                    && (1..6).contains(&sj)
+                    && invader_nbr & ((1 << (si * sj)) as u16) > 0;
+                put(c.x as u32 * size + i, c.y as u32 * size + j, lit);
             }
         }
     }
@@ -49,28 +42,47 @@ impl RendererInvaders {
 
 impl Rendering for RendererInvaders {
     fn tile_size(&self) -> usize {
-        TILE_SIZE as usize
+        self.tile_size
     }
-    fn draw_cell(&self, _maze: &Maze, img: &mut RgbImage, c: &Coord, cell_kind: CellKind) {
+    fn draw_cell(&self, maze: &Maze, img: &mut RgbImage, c: &Coord, cell_kind: CellKind) {
         match cell_kind {
-            CellKind::PathKind(_) => self.draw_invader(img, c),
-            CellKind::WallKind => {
-                draw_cell_plain(self, img, c, &self.wall_color);
-            }
-            _ => {}
+            CellKind::PathKind(_) => self.invader_pixels(maze, c, |x, y, lit| {
+                let color = if lit {
+                    self.invader_color
+                } else {
+                    self.wall_color
+                };
+                img.put_pixel(x, y, color);
+            }),
+            /* cells not generated yet look like walls, as in gif frames */
+            _ => draw_cell_plain(self, img, c, &self.wall_color),
         }
     }
     fn draw_cell_gif(
         &self,
-        _maze: &Maze,
-        _img_geom: &super::Geometry,
-        _buffer: &mut Vec<u8>,
-        _c: &Coord,
-        _cell_kind: CellKind,
+        maze: &Maze,
+        img_geom: &super::Geometry,
+        buffer: &mut Vec<u8>,
+        c: &Coord,
+        cell_kind: CellKind,
     ) {
-        unimplemented!();
+        match cell_kind {
+            CellKind::PathKind(_) => self.invader_pixels(maze, c, |x, y, lit| {
+                buffer[x as usize + y as usize * img_geom.width] = lit as u8;
+            }),
+            _ => draw_cell_plain_gif(self, img_geom.width, buffer, c, 0),
+        }
     }
     fn get_gif_palette(&self) -> Vec<u8> {
-        unimplemented!();
+        let mut palette = Vec::new();
+        palette.extend_from_slice(&self.wall_color.0);
+        palette.extend_from_slice(&self.invader_color.0);
+        palette
+    }
+    fn cell_color(&self, cell_kind: &CellKind) -> Rgb<u8> {
+        match *cell_kind {
+            CellKind::PathKind(_) => self.invader_color,
+            _ => self.wall_color,
+        }
     }
 }
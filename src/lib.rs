@@ -0,0 +1,712 @@
+//! Maze generation library.
+//!
+//! Mazes are described with a [`MazeBuilder`] and generated either as an
+//! in-memory [`Maze`], as an [`RgbImage`] or straight into a file.
+//!
+//! ```no_run
+//! use maze::{AlgorithmKind, Geometry, MazeBuilder};
+//!
+//! let img = MazeBuilder::new()
+//!     .geometry(Geometry {
+//!         width: 640,
+//!         height: 400,
+//!     })
+//!     .algorithm(AlgorithmKind::Kruskal)
+//!     .render();
+//! img.save("maze.png").unwrap();
+//! ```
+
+extern crate color_scaling;
+extern crate gif;
+extern crate image;
+extern crate image_webp;
+extern crate png;
+extern crate rand;
+
+pub mod invaders;
+pub mod lines;
+pub mod maze;
+pub mod mosaic;
+pub mod plain;
+pub mod solver;
+pub mod stats;
+pub mod svg;
+pub mod text;
+
+use image::{ImageResult, Rgb, RgbImage};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path;
+
+use crate::maze::AnimationSettings;
+
+pub use crate::maze::{
+    AlgorithmKind, CellKind, Coord, Gradient, GrowingTreePolicy, Maze, Rendering,
+};
+
+#[derive(Debug, Clone)]
+pub struct Geometry {
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Origin {
+    pub x: f64,
+    pub y: f64,
+}
+
+/// Size in pixels of an output image and position of the maze in it. The
+/// maze may not fill the image exactly, the rest is filled with the
+/// background color.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub geometry: Geometry,
+    pub left: usize,
+    pub top: usize,
+}
+
+/// Where the maze is placed in the image when it does not fill it exactly.
+#[derive(Debug, Clone, Copy)]
+pub enum Align {
+    Center,
+    TopLeft,
+}
+
+/* Builder {{{ */
+
+/// Describes a maze to generate.
+///
+/// The geometry is the size in pixels of the image to render; the size of
+/// the maze grid is derived from it using the tile size of the renderer. The
+/// rendered image has this geometry, the pixels left around the maze being
+/// filled with the background color. The maze has at least one cell: if the
+/// geometry is too small to hold it inside the margins, the image is
+/// enlarged to fit them.
+pub struct MazeBuilder {
+    geometry: Geometry,
+    vertical_bias: f64,
+    origin: Origin,
+    distance_from: Option<Origin>,
+    gradient: Gradient,
+    algorithm: AlgorithmKind,
+    renderer: Box<dyn Rendering>,
+    seed: Option<u64>,
+    braid: f64,
+    ansi: bool,
+    margin: usize,
+    align: Align,
+    animation: AnimationSettings,
+    solution_color: Option<Rgb<u8>>,
+    markers: Option<[Rgb<u8>; 2]>,
+}
+
+/* Grid dimensions are kept odd so that the maze ends with a row and a column
+ * of cells, like it starts, instead of a row or column of walls */
+fn odd_geometry(g: &Geometry) -> Geometry {
+    Geometry {
+        width: g.width.saturating_sub(1) | 1,
+        height: g.height.saturating_sub(1) | 1,
+    }
+}
+
+impl Default for MazeBuilder {
+    fn default() -> Self {
+        MazeBuilder {
+            geometry: Geometry {
+                width: 100,
+                height: 100,
+            },
+            vertical_bias: 0.5,
+            origin: Origin { x: 0.0, y: 0.0 },
+            distance_from: None,
+            gradient: Gradient::Length,
+            algorithm: AlgorithmKind::Prim,
+            renderer: Box::new(plain::RendererPlain {
+                path_color_start: Rgb([0xd7, 0x00, 0x00]),
+                path_color_end: Rgb([0xff, 0xff, 0xd7]),
+                wall_color: Rgb([0x07, 0x36, 0x42]),
+                tile_size: plain::TILE_SIZE,
+            }),
+            seed: None,
+            braid: 0.0,
+            ansi: false,
+            margin: 0,
+            align: Align::Center,
+            animation: AnimationSettings::default(),
+            solution_color: None,
+            markers: None,
+        }
+    }
+}
+
+impl MazeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Size in pixels of the image to generate.
+    pub fn geometry(mut self, geometry: Geometry) -> Self {
+        self.geometry = geometry;
+        self
+    }
+
+    /// Larger than 0.5, the maze will tend to be more vertical. Lower than
+    /// 0.5, it will tend to be more horizontal.
+    pub fn vertical_bias(mut self, vertical_bias: f64) -> Self {
+        self.vertical_bias = vertical_bias;
+        self
+    }
+
+    /// Relative origin of the maze. Middle is `Origin { x: 0.5, y: 0.5 }`.
+    pub fn origin(mut self, origin: Origin) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Relative point from which distances are measured with
+    /// [`Gradient::Length`]. Defaults to the origin.
+    pub fn distance_from(mut self, from: Origin) -> Self {
+        self.distance_from = Some(from);
+        self
+    }
+
+    pub fn gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = gradient;
+        self
+    }
+
+    pub fn algorithm(mut self, algorithm: AlgorithmKind) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    pub fn renderer(mut self, renderer: Box<dyn Rendering>) -> Self {
+        self.renderer = renderer;
+        self
+    }
+
+    /// Seed of the random number generator. Generating twice with the same
+    /// seed and settings gives the same maze. When unset, a random seed is
+    /// picked and can be retrieved with [`Maze::seed`].
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Minimal space in pixels between the maze and the edges of the image.
+    /// The image is enlarged when the margins leave no room for a cell.
+    pub fn margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Where to place the maze in the image.
+    pub fn align(mut self, align: Align) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// Draw a frame of animations every `frame_every` steps of the
+    /// algorithm only.
+    pub fn frame_every(mut self, frame_every: u32) -> Self {
+        self.animation.frame_every = frame_every;
+        self
+    }
+
+    /// Maximum number of frames of animations showing the generation of the
+    /// maze, the final frame aside. Frames are drawn less often if needed.
+    pub fn max_frames(mut self, max_frames: u32) -> Self {
+        self.animation.max_frames = Some(max_frames);
+        self
+    }
+
+    /// Frames per second of animations.
+    pub fn fps(mut self, fps: f64) -> Self {
+        self.animation.fps = fps;
+        self
+    }
+
+    /// Time in seconds the complete maze is shown at the end of animations.
+    pub fn final_hold(mut self, final_hold: f64) -> Self {
+        self.animation.final_hold = final_hold;
+        self
+    }
+
+    /// Whether animations loop forever or play once.
+    pub fn loop_forever(mut self, loop_forever: bool) -> Self {
+        self.animation.loop_forever = loop_forever;
+        self
+    }
+
+    /// Continue animations with the maze being solved by a breadth-first
+    /// search from the origin to the end, drawing the cells it visits and
+    /// then the solution with the given colors.
+    pub fn animate_solve(mut self, visited: Rgb<u8>, solution: Rgb<u8>) -> Self {
+        self.animation.solve = Some([visited, solution]);
+        self
+    }
+
+    /// Draw the solution, from the origin to the end, in `color` on top of
+    /// images and SVG documents, as an answer key.
+    pub fn show_solution(mut self, color: Rgb<u8>) -> Self {
+        self.solution_color = Some(color);
+        self
+    }
+
+    /// Mark the origin and the end of the maze, with the colors `start` and
+    /// `end`, on top of images and SVG documents.
+    pub fn markers(mut self, start: Rgb<u8>, end: Rgb<u8>) -> Self {
+        self.markers = Some([start, end]);
+        self
+    }
+
+    /* Cells drawn on top of the maze with a flat color, in order */
+    fn overlay(&self, maze: &Maze) -> Vec<(Vec<Coord>, Rgb<u8>)> {
+        let mut overlay = Vec::new();
+        if let Some(color) = self.solution_color {
+            overlay.push((maze.solution(), color));
+        }
+        if let Some([start, end]) = self.markers {
+            overlay.push((vec![maze.origin()], start));
+            overlay.push((vec![maze.end()], end));
+        }
+        overlay
+    }
+
+    /* Size of the grid fitting in the image, inside the margins */
+    fn grid_geometry(&self) -> Geometry {
+        let inner = Geometry {
+            width: self.geometry.width.saturating_sub(2 * self.margin),
+            height: self.geometry.height.saturating_sub(2 * self.margin),
+        };
+        odd_geometry(&maze::grid_geometry(&*self.renderer, &inner))
+    }
+
+    fn new_maze(&self) -> Maze {
+        self.new_maze_on(&self.grid_geometry())
+    }
+
+    fn new_maze_on(&self, grid_geometry: &Geometry) -> Maze {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        Maze::new(grid_geometry, self.vertical_bias, &self.origin, seed)
+    }
+
+    /// Fraction of the dead ends to remove once the maze is generated,
+    /// adding loops to it. A value of 0 keeps a perfect maze, with a single
+    /// route between any two cells.
+    pub fn braid(mut self, braid: f64) -> Self {
+        self.braid = braid;
+        self
+    }
+
+    /// Color text output with 24-bit ANSI escape codes.
+    pub fn ansi(mut self, ansi: bool) -> Self {
+        self.ansi = ansi;
+        self
+    }
+
+    fn post_process(&self, maze: &mut Maze) {
+        if self.braid > 0.0 {
+            maze.braid(self.braid);
+        }
+        match self.gradient {
+            Gradient::Length => {
+                maze.compute_distances(self.distance_from.as_ref().unwrap_or(&self.origin))
+            }
+            Gradient::Solution => maze.compute_solution(),
+        }
+    }
+
+    /// Place a maze of `grid` cells in the output image.
+    fn layout(&self, grid: &Geometry) -> Layout {
+        let maze_geometry = maze::image_geometry(&*self.renderer, grid);
+        let geometry = Geometry {
+            width: self
+                .geometry
+                .width
+                .max(maze_geometry.width + 2 * self.margin),
+            height: self
+                .geometry
+                .height
+                .max(maze_geometry.height + 2 * self.margin),
+        };
+        let (left, top) = match self.align {
+            Align::Center => (
+                (geometry.width - maze_geometry.width) / 2,
+                (geometry.height - maze_geometry.height) / 2,
+            ),
+            Align::TopLeft => (self.margin, self.margin),
+        };
+        Layout {
+            geometry,
+            left,
+            top,
+        }
+    }
+
+    /// Generate the maze, with path values set according to the gradient.
+    pub fn build(&self) -> Maze {
+        let mut maze = self.new_maze();
+        maze::generate(&mut maze, self.algorithm, |_| {});
+        self.post_process(&mut maze);
+        maze
+    }
+
+    /// Draw a maze generated by this builder with its renderer, and the
+    /// solution and markers if enabled.
+    pub fn draw(&self, maze: &Maze) -> RgbImage {
+        let layout = self.layout(&maze.geometry());
+        let mut img = maze.draw_layout(&*self.renderer, &layout);
+        for (cells, color) in self.overlay(maze) {
+            maze::paint_cells(&mut img, &*self.renderer, &layout, &cells, color);
+        }
+        img
+    }
+
+    /// Generate the maze for text output. A character shows two cells
+    /// stacked vertically, so the geometry is taken as the number of
+    /// characters per line by twice the number of lines, rounded down to
+    /// odd numbers.
+    pub fn build_text(&self) -> Maze {
+        let mut maze = self.new_maze_on(&odd_geometry(&self.geometry));
+        maze::generate(&mut maze, self.algorithm, |_| {});
+        self.post_process(&mut maze);
+        maze
+    }
+
+    /// Draw a maze as text, using the colors of the renderer if ANSI colors
+    /// are enabled.
+    pub fn draw_text(&self, maze: &Maze) -> String {
+        text::draw_text(maze, &*self.renderer, self.ansi)
+    }
+
+    /// Generate the maze and draw it.
+    pub fn render(&self) -> RgbImage {
+        self.draw(&self.build())
+    }
+
+    /// Draw a maze generated by this builder as an SVG document, using the
+    /// colors of its renderer.
+    pub fn draw_svg(&self, maze: &Maze) -> String {
+        svg::draw_svg(
+            maze,
+            &*self.renderer,
+            &self.layout(&maze.geometry()),
+            &self.overlay(maze),
+        )
+    }
+
+    /// Generate the maze and save it to `path`. The format is picked from
+    /// the extension of `path`: text for `.txt`, SVG for `.svg`, a raster
+    /// image otherwise. A `path` of `-` writes text to the standard output.
+    pub fn save(&self, path: &path::Path) -> ImageResult<Maze> {
+        let extension = path.extension().and_then(|e| e.to_str());
+        if path == path::Path::new("-") {
+            let maze = self.build_text();
+            io::stdout().write_all(self.draw_text(&maze).as_bytes())?;
+            return Ok(maze);
+        }
+        if let Some("txt") = extension {
+            let maze = self.build_text();
+            fs::write(path, self.draw_text(&maze))?;
+            return Ok(maze);
+        }
+        let maze = self.build();
+        match extension {
+            Some("svg") => fs::write(path, self.draw_svg(&maze))?,
+            _ => self.draw(&maze).save(path)?,
+        }
+        Ok(maze)
+    }
+
+    /// Generate the maze and save the animation of its generation to
+    /// `path`. The format is picked from the extension of `path`: an
+    /// animated PNG for `.png` or `.apng`, an animated WebP for `.webp`, a
+    /// gif otherwise. Unlike gif, animated PNG and WebP keep the full colors
+    /// of the gradient. Gif images larger than 65535 pixels are rejected
+    /// with an [`io::ErrorKind::InvalidInput`] error, before creating the
+    /// file.
+    pub fn animate(&self, path: &path::Path) -> io::Result<Maze> {
+        let mut maze = self.new_maze();
+        let layout = self.layout(&maze.geometry());
+        let extension = path.extension().and_then(|e| e.to_str());
+        let is_gif = !matches!(extension, Some("png") | Some("apng") | Some("webp"));
+        let max = u16::MAX as usize;
+        if is_gif && (layout.geometry.width > max || layout.geometry.height > max) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "gif images can not be larger than 65535 pixels",
+            ));
+        }
+        let mut file = BufWriter::new(File::create(path)?);
+        let post_process = |m: &mut Maze| self.post_process(m);
+        let renderer = &*self.renderer;
+        let settings = &self.animation;
+        match extension {
+            Some("png") | Some("apng") => maze::generate_apng(
+                &mut maze,
+                renderer,
+                self.algorithm,
+                &layout,
+                settings,
+                &mut file,
+                post_process,
+            ),
+            Some("webp") => maze::generate_webp(
+                &mut maze,
+                renderer,
+                self.algorithm,
+                &layout,
+                settings,
+                &mut file,
+                post_process,
+            ),
+            _ => maze::generate_animation(
+                &mut maze,
+                renderer,
+                self.algorithm,
+                &layout,
+                settings,
+                &mut file,
+                post_process,
+            ),
+        }?;
+        file.flush()?;
+        Ok(maze)
+    }
+
+    /// Generate the maze with Eller's algorithm and write it as a PNG image
+    /// to `path` row by row, without keeping the whole maze in memory. This
+    /// makes it possible to generate very large mazes, but the paths are
+    /// drawn without gradient. Returns the seed used.
+    pub fn stream(&self, path: &path::Path) -> io::Result<u64> {
+        let grid_geometry = self.grid_geometry();
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let mut file = BufWriter::new(File::create(path)?);
+        maze::generate_streaming(
+            &grid_geometry,
+            &*self.renderer,
+            self.vertical_bias,
+            seed,
+            &self.layout(&grid_geometry),
+            &mut file,
+        )?;
+        file.flush()?;
+        Ok(seed)
+    }
+}
+
+/* }}} */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALGORITHMS: [AlgorithmKind; 12] = [
+        AlgorithmKind::Prim,
+        AlgorithmKind::Kruskal,
+        AlgorithmKind::Backtracker,
+        AlgorithmKind::HuntAndKill,
+        AlgorithmKind::Wilson,
+        AlgorithmKind::AldousBroder { wilson_ratio: None },
+        AlgorithmKind::AldousBroder {
+            wilson_ratio: Some(0.5),
+        },
+        AlgorithmKind::Eller,
+        AlgorithmKind::GrowingTree(GrowingTreePolicy {
+            newest: 1,
+            oldest: 0,
+            random: 1,
+            middle: 0,
+        }),
+        AlgorithmKind::RecursiveDivision,
+        AlgorithmKind::BinaryTree { diagonal_bias: 0.5 },
+        AlgorithmKind::Sidewinder {
+            close_probability: 0.3,
+        },
+    ];
+
+    fn same_grid(a: &Maze, b: &Maze) -> bool {
+        let g = a.geometry();
+        (0..g.height).all(|y| {
+            (0..g.width).all(|x| {
+                let c = Coord { x, y };
+                match (a.cell_kind(&c), b.cell_kind(&c)) {
+                    (CellKind::WallKind, CellKind::WallKind) => true,
+                    (CellKind::PathKind(f1), CellKind::PathKind(f2)) => f1 == f2,
+                    (CellKind::Undefined, CellKind::Undefined) => true,
+                    _ => false,
+                }
+            })
+        })
+    }
+
+    fn builder(algorithm: AlgorithmKind, seed: u64) -> MazeBuilder {
+        MazeBuilder::new()
+            .geometry(Geometry {
+                width: 120,
+                height: 80,
+            })
+            .algorithm(algorithm)
+            .seed(seed)
+    }
+
+    #[test]
+    fn same_seed_same_maze() {
+        for algorithm in ALGORITHMS {
+            let builder = builder(algorithm, 42);
+            let (a, b) = (builder.build(), builder.build());
+            assert!(same_grid(&a, &b), "{:?}", algorithm);
+            assert_eq!((a.end().x, a.end().y), (b.end().x, b.end().y));
+            assert!(builder.draw(&a) == builder.draw(&b), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn other_seed_other_maze() {
+        for algorithm in ALGORITHMS {
+            let a = builder(algorithm, 1).build();
+            let b = builder(algorithm, 2).build();
+            assert!(!same_grid(&a, &b), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn stream_same_as_draw() {
+        let white = Rgb([255, 255, 255]);
+        let black = Rgb([0, 0, 0]);
+        let renderers: [Box<dyn Rendering>; 3] = [
+            Box::new(plain::RendererPlain {
+                path_color_start: white,
+                path_color_end: white,
+                wall_color: black,
+                tile_size: plain::TILE_SIZE,
+            }),
+            Box::new(invaders::RendererInvaders {
+                invader_color: white,
+                wall_color: black,
+                tile_size: invaders::TILE_SIZE,
+            }),
+            Box::new(mosaic::RendererMosaic {
+                is_inverted: false,
+                tile_size: mosaic::TILE_SIZE,
+            }),
+        ];
+        let path = std::env::temp_dir().join(format!("maze-stream-{}.png", std::process::id()));
+        for renderer in renderers {
+            let builder = builder(AlgorithmKind::Eller, 7).renderer(renderer);
+            builder.stream(&path).unwrap();
+            let streamed = image::open(&path).unwrap().to_rgb8();
+            assert!(streamed == builder.draw(&builder.build()));
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn exact_geometry() {
+        let white = Rgb([255, 255, 255]);
+        let black = Rgb([0, 0, 0]);
+        let renderers = || -> [Box<dyn Rendering>; 4] {
+            [
+                Box::new(plain::RendererPlain {
+                    path_color_start: white,
+                    path_color_end: black,
+                    wall_color: black,
+                    tile_size: plain::TILE_SIZE,
+                }),
+                Box::new(invaders::RendererInvaders {
+                    invader_color: white,
+                    wall_color: black,
+                    tile_size: invaders::TILE_SIZE,
+                }),
+                Box::new(mosaic::RendererMosaic {
+                    is_inverted: false,
+                    tile_size: mosaic::TILE_SIZE,
+                }),
+                Box::new(lines::RendererLines {
+                    colors: plain::RendererPlain {
+                        path_color_start: white,
+                        path_color_end: black,
+                        wall_color: black,
+                        tile_size: 10,
+                    },
+                    cell_size: 10,
+                    wall_thickness: 2,
+                }),
+            ]
+        };
+        for (width, height) in [(100, 100), (123, 77), (640, 401), (41, 43)] {
+            for margin in [0, 5, 13] {
+                for align in [Align::Center, Align::TopLeft] {
+                    for renderer in renderers() {
+                        let builder = builder(AlgorithmKind::Backtracker, 1)
+                            .geometry(Geometry { width, height })
+                            .renderer(renderer)
+                            .margin(margin)
+                            .align(align);
+                        let img = builder.render();
+                        assert_eq!(img.dimensions(), (width as u32, height as u32));
+                    }
+                }
+            }
+        }
+
+        /* margins leaving no room for a cell */
+        let img = builder(AlgorithmKind::Backtracker, 1)
+            .geometry(Geometry {
+                width: 100,
+                height: 100,
+            })
+            .margin(60)
+            .render();
+        assert_eq!(img.dimensions(), (124, 124));
+    }
+
+    #[test]
+    fn gif_too_large() {
+        let path = std::env::temp_dir().join(format!("maze-large-{}.gif", std::process::id()));
+        let err = MazeBuilder::new()
+            .geometry(Geometry {
+                width: 70000,
+                height: 8,
+            })
+            .animate(&path)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn maze_is_send_and_sync() {
+        fn shareable<T: Send + Sync>() {}
+        shareable::<Maze>();
+    }
+
+    #[test]
+    fn overlay() {
+        let red = Rgb([255, 0, 0]);
+        let (start, end) = (Rgb([0, 255, 0]), Rgb([0, 0, 255]));
+        let builder = builder(AlgorithmKind::Prim, 9)
+            .margin(6)
+            .show_solution(red)
+            .markers(start, end);
+        let maze = builder.build();
+        let img = builder.draw(&maze);
+        let layout = builder.layout(&maze.geometry());
+        let pixel = |c: &Coord| {
+            let x = layout.left + builder.renderer.tile_offset(c.x);
+            let y = layout.top + builder.renderer.tile_offset(c.y);
+            *img.get_pixel(x as u32, y as u32)
+        };
+        let solution = maze.solution();
+        for c in &solution[1..solution.len() - 1] {
+            assert_eq!(pixel(c), red);
+        }
+        assert_eq!(pixel(&maze.origin()), start);
+        assert_eq!(pixel(&maze.end()), end);
+        let painted = img.pixels().filter(|p| **p == red).count();
+        let tile = builder.renderer.tile_size();
+        assert_eq!(painted, (solution.len() - 2) * tile * tile);
+    }
+}
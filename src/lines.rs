@@ -0,0 +1,132 @@
+use crate::maze::{CellKind, Coord, Maze, Rendering};
+use crate::plain::RendererPlain;
+use image::{Rgb, RgbImage};
+
+/* Classic printed maze style: cells are wide squares and walls are thin
+ * lines between them, with a frame around the maze. Cells of the grid at
+ * even coordinates take `cell_size` pixels, walls at odd coordinates
+ * `wall_thickness` pixels. Colors are the ones of the plain renderer. */
+pub struct RendererLines {
+    pub colors: RendererPlain,
+    pub cell_size: usize,
+    pub wall_thickness: usize,
+}
+
+impl RendererLines {
+    /* Pixels covered by the column or row `i` of a grid of `n` columns or
+     * rows, including the frame when on the border */
+    fn span(&self, i: usize, n: usize) -> (usize, usize) {
+        let start = if i == 0 { 0 } else { self.tile_offset(i) };
+        let end = if i + 1 == n {
+            self.image_length(n)
+        } else {
+            self.tile_offset(i + 1)
+        };
+        (start, end)
+    }
+
+    /* Pixels of the frame around the column or row `i` */
+    fn is_frame(&self, p: usize, i: usize, n: usize) -> bool {
+        (i == 0 && p < self.tile_offset(0)) || (i + 1 == n && p >= self.tile_offset(n))
+    }
+}
+
+impl Rendering for RendererLines {
+    fn tile_size(&self) -> usize {
+        self.cell_size
+    }
+    fn tile_offset(&self, i: usize) -> usize {
+        self.wall_thickness
+            + (i / 2) * (self.cell_size + self.wall_thickness)
+            + (i % 2) * self.cell_size
+    }
+    fn image_length(&self, n: usize) -> usize {
+        /* A grid ending with a column of cells gets a closing frame */
+        self.tile_offset(n) + (n % 2) * self.wall_thickness
+    }
+    fn grid_length(&self, pixels: usize) -> usize {
+        let nb_cells =
+            pixels.saturating_sub(self.wall_thickness) / (self.cell_size + self.wall_thickness);
+        (2 * nb_cells).saturating_sub(1).max(1)
+    }
+    fn draw_cell(&self, maze: &Maze, img: &mut RgbImage, c: &Coord, cell_kind: CellKind) {
+        let g = maze.geometry();
+        let color = self.cell_color(&cell_kind);
+        let wall_color = self.cell_color(&CellKind::WallKind);
+        let (x0, x1) = self.span(c.x, g.width);
+        let (y0, y1) = self.span(c.y, g.height);
+        for x in x0..x1 {
+            for y in y0..y1 {
+                let p = if self.is_frame(x, c.x, g.width) || self.is_frame(y, c.y, g.height) {
+                    wall_color
+                } else {
+                    color
+                };
+                img.put_pixel(x as u32, y as u32, p);
+            }
+        }
+    }
+    fn draw_cell_gif(
+        &self,
+        maze: &Maze,
+        img_geom: &super::Geometry,
+        buffer: &mut Vec<u8>,
+        c: &Coord,
+        cell_kind: CellKind,
+    ) {
+        let g = maze.geometry();
+        let color_idx = self.colors.gif_color_index(&cell_kind);
+        let wall_idx = self.colors.gif_color_index(&CellKind::WallKind);
+        let (x0, x1) = self.span(c.x, g.width);
+        let (y0, y1) = self.span(c.y, g.height);
+        for x in x0..x1 {
+            for y in y0..y1 {
+                buffer[x + y * img_geom.width] =
+                    if self.is_frame(x, c.x, g.width) || self.is_frame(y, c.y, g.height) {
+                        wall_idx
+                    } else {
+                        color_idx
+                    };
+            }
+        }
+    }
+    fn get_gif_palette(&self) -> Vec<u8> {
+        self.colors.get_gif_palette()
+    }
+    fn cell_color(&self, cell_kind: &CellKind) -> Rgb<u8> {
+        self.colors.cell_color(cell_kind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lengths_round_trip() {
+        for (cell_size, wall_thickness) in [(10, 2), (4, 1), (7, 3)] {
+            let renderer = RendererLines {
+                colors: RendererPlain {
+                    path_color_start: Rgb([255, 255, 255]),
+                    path_color_end: Rgb([255, 255, 255]),
+                    wall_color: Rgb([0, 0, 0]),
+                    tile_size: cell_size,
+                },
+                cell_size,
+                wall_thickness,
+            };
+            for n in (1..50).step_by(2) {
+                let pixels = renderer.image_length(n);
+                assert_eq!(
+                    pixels,
+                    n.div_ceil(2) * (cell_size + wall_thickness) + wall_thickness
+                );
+                assert_eq!(renderer.grid_length(pixels), n);
+                /* one pixel short does not fit the last cell */
+                if n > 1 {
+                    assert_eq!(renderer.grid_length(pixels - 1), n - 2);
+                }
+            }
+        }
+    }
+}
@@ -1,39 +1,31 @@
-extern crate color_scaling;
 extern crate docopt;
-extern crate gif;
 extern crate image;
-extern crate rand;
+extern crate maze;
 extern crate read_color;
 
-mod invaders;
-mod maze;
-mod mosaic;
-mod plain;
-
-#[derive(Debug, Clone)]
-pub struct Geometry {
-    width: usize,
-    height: usize,
-}
-
 use docopt::Docopt;
+use image::{ImageError, Rgb};
+use maze::{invaders, lines, mosaic, plain, solver, stats, Align, Geometry, MazeBuilder, Origin};
 use std::path;
 use std::str::FromStr;
 
-#[derive(Debug)]
-pub struct Origin {
-    x: f64,
-    y: f64,
-}
-
-use image::Rgb;
-
 /* CLI {{{ */
 
 const USAGE: &str = "
 Maze background generator.
 
-Usage: maze [options] FILE
+The format of FILE is picked from its extension: text for .txt, SVG for
+.svg, a raster image otherwise. A FILE of - writes text to the standard
+output. For text, the geometry is the number of characters per line by twice
+the number of lines. With --animation, FILE is an animated PNG for .png or
+.apng, an animated WebP for .webp, a GIF otherwise.
+
+`maze solve` generates the maze without saving it and reports, for each
+solver, the length of the path found, or - if none was, the number of steps,
+the number of distinct cells visited and the time taken.
+
+Usage: maze solve [options]
+       maze [options] FILE
        maze --geometry GEOM FILE
        maze -g GEOM FILE
        maze --rendering=RENDERING FILE
@@ -42,11 +34,23 @@ Usage: maze [options] FILE
        maze -b BIAS FILE
        maze -o ORIGIN FILE
        maze --origin ORIGIN FILE
+       maze --distance-from POINT FILE
        maze --foreground COLOR FILE
        maze --background COLOR FILE
        maze --gradient GRADIENT FILE
        maze --algorithm ALGORITHM FILE
+       maze --seed SEED FILE
+       maze --stream FILE
+       maze --braid RATIO FILE
+       maze --ansi FILE
+       maze --stats --stats-json FILE
+       maze --tile-size SIZE FILE
+       maze --margin PIXELS --align ALIGN FILE
+       maze --cell-size SIZE --wall-thickness SIZE FILE
        maze --animation
+       maze --animation --frame-every N --max-frames N --fps FPS --final-hold SECONDS --loop LOOP FILE
+       maze --animation --animate-solve --solve-colors COLORS FILE
+       maze --show-solution COLOR --markers COLORS FILE
        maze -h | --help
        maze -v | --version
 
@@ -54,14 +58,35 @@ Options:
     -h, --help                                    Show this message
     -v, --version                                 Show the version
     -g=<WIDTHxHEIGHT>, --geometry=<WIDTHxHEIGHT>  Geometry of the image to generate [default: 100x100]
-    -r=RENDERING, --rendering=RENDERING           Rendering mode. Valid values are: plain, invaders, mosaic. [default: plain]
+    -r=RENDERING, --rendering=RENDERING           Rendering mode. Valid values are: plain, invaders, mosaic, lines. [default: plain]
     -b=BIAS, --vertical-bias=BIAS                 Vertical Bias. Larger than 0.5, the maze will then to be more vertical. Lower than 0.5, will tend to be more horizontal. [default: 0.5]
     -o=ORIGIN, --origin=ORIGIN                    Relative origin of the maze in floating point coordinates. Middle is 0.5x0.5. [default: 0.0x0.0]
+    --distance-from=POINT                         Relative point from which the length gradient measures distances, like the origin. Defaults to the origin.
     --background=COLOR                            Background color. [default: #073642]
     --foreground=COLOR                            Foreground color(s). Either one or two colors (\"#ffffff\" or \"#ffffff #ff00ff\"). [default: #d70000 #ffffd7]
     --gradient=GRADIENT                           If 2 foreground colors, define how to do the gradient. Valid values are: length, solution. [default: length]
-    --algorithm=ALGORITHM                         Algorithm used to generate the maze. Valid values are: prim, kruskal. [default: prim]
+    --algorithm=ALGORITHM                         Algorithm used to generate the maze. Valid values are: prim, kruskal, backtracker, hunt-and-kill, wilson, aldous-broder, eller, growing-tree, recursive-division, binary-tree, sidewinder. aldous-broder:RATIO switches to wilson once RATIO of the cells are visited. growing-tree:POLICY picks cells with weights among newest, oldest, random and middle (\"growing-tree:newest:75,random:25\"). binary-tree:BIAS goes up rather than left with a probability of BIAS. sidewinder:PROBABILITY closes runs with a probability of PROBABILITY. [default: prim]
     --animation                                   Render an animation as the maze is being generated
+    --frame-every=N                               Draw a frame of the animation every N steps of the algorithm. [default: 1]
+    --max-frames=N                                Maximum number of frames showing the generation, drawing them less often if needed.
+    --fps=FPS                                     Frames per second of the animation. [default: 50]
+    --final-hold=SECONDS                          Time the complete maze is shown at the end of the animation. [default: 1.0]
+    --loop=LOOP                                   Whether the animation loops. Valid values are: once, forever. [default: forever]
+    --animate-solve                               Continue the animation with the maze being solved from the origin to the end.
+    --solve-colors=COLORS                         Colors of the cells visited while solving the maze and of the solution. [default: #6c71c4 #859900]
+    --show-solution=COLOR                         Draw the solution from the origin to the end with COLOR, on images and SVG.
+    --markers=COLORS                              Mark the origin and the end of the maze with two colors (\"#859900 #dc322f\"), on images and SVG.
+    --stream                                      Write the maze row by row as it is generated, without keeping it in memory. Requires the eller algorithm and a PNG FILE.
+    --braid=RATIO                                 Fraction of the dead ends to remove, between 0.0 and 1.0, adding loops to the maze. [default: 0.0]
+    --tile-size=SIZE                              Size in pixels of the tiles with the plain, invaders and mosaic renderings. Defaults to 4, 7 and 5 respectively.
+    --margin=PIXELS                               Minimal space between the maze and the edges of the image, filled with the background color. The image is enlarged when the margins leave no room for a cell. [default: 0]
+    --align=ALIGN                                 Where to place the maze in the image when it does not fill it exactly. Valid values are: center, topleft. [default: center]
+    --cell-size=SIZE                              Size in pixels of the cells with the lines rendering. [default: 8]
+    --wall-thickness=SIZE                         Thickness in pixels of the walls with the lines rendering. [default: 2]
+    --ansi                                        Color text output with 24-bit ANSI escape codes.
+    --stats                                       Print statistics of the maze: dead ends, junctions, corridors, solution length, branch length, river factor and turns.
+    --stats-json                                  Print the statistics of the maze as JSON.
+    --seed=SEED                                   Seed of the random number generator, to reproduce a maze. Random if not set.
 ";
 
 fn geometry_parse(geometry: &str) -> Geometry {
@@ -74,24 +99,62 @@ fn geometry_parse(geometry: &str) -> Geometry {
     Geometry { width, height }
 }
 
-fn rendering_parse(rendering: &str, bg: Rgb<u8>, fg: [Rgb<u8>; 2]) -> Box<dyn maze::Rendering> {
+fn rendering_parse(
+    rendering: &str,
+    bg: Rgb<u8>,
+    fg: [Rgb<u8>; 2],
+    tile_size: Option<usize>,
+    cell_size: usize,
+    wall_thickness: usize,
+) -> Box<dyn maze::Rendering> {
     match rendering {
         "plain" => Box::new(plain::RendererPlain {
             path_color_start: fg[0],
             path_color_end: fg[1],
             wall_color: bg,
+            tile_size: tile_size.unwrap_or(plain::TILE_SIZE),
         }),
         "invaders" => Box::new(invaders::RendererInvaders {
             invader_color: fg[0],
             wall_color: bg,
+            tile_size: tile_size.unwrap_or(invaders::TILE_SIZE),
+        }),
+        "mosaic" => Box::new(mosaic::RendererMosaic {
+            is_inverted: false,
+            tile_size: tile_size.unwrap_or(mosaic::TILE_SIZE),
+        }),
+        "lines" => Box::new(lines::RendererLines {
+            colors: plain::RendererPlain {
+                path_color_start: fg[0],
+                path_color_end: fg[1],
+                wall_color: bg,
+                tile_size: cell_size,
+            },
+            cell_size,
+            wall_thickness,
         }),
-        "mosaic" => Box::new(mosaic::RendererMosaic { is_inverted: false }),
         _ => {
             panic!("invalid rendering mode")
         }
     }
 }
 
+fn align_parse(align: &str) -> Align {
+    match align {
+        "center" => Align::Center,
+        "topleft" => Align::TopLeft,
+        _ => panic!("invalid alignment"),
+    }
+}
+
+fn size_parse(size: &str) -> usize {
+    let d = usize::from_str(size).expect("size is not an unsigned integer");
+    if d == 0 {
+        panic!("size must be at least 1");
+    }
+    d
+}
+
 fn vertical_bias_parse(vertical_bias: &str) -> f64 {
     let d = f64::from_str(vertical_bias).expect("vertical_bias is not a floating number");
 
@@ -103,6 +166,46 @@ fn vertical_bias_parse(vertical_bias: &str) -> f64 {
     d
 }
 
+fn braid_parse(braid: &str) -> f64 {
+    let d = f64::from_str(braid).expect("braid is not a floating number");
+    if !(0.0..=1.0).contains(&d) {
+        panic!("braid must be between 0.0 and 1.0");
+    }
+    d
+}
+
+fn count_parse(count: &str) -> u32 {
+    let d = u32::from_str(count).expect("count is not an unsigned integer");
+    if d == 0 {
+        panic!("count must be at least 1");
+    }
+    d
+}
+
+fn duration_parse(duration: &str) -> f64 {
+    let d = f64::from_str(duration).expect("duration is not a floating number");
+    if !(d >= 0.0 && d.is_finite()) {
+        panic!("duration must be positive");
+    }
+    d
+}
+
+fn fps_parse(fps: &str) -> f64 {
+    let d = f64::from_str(fps).expect("fps is not a floating number");
+    if !(d > 0.0 && d.is_finite()) {
+        panic!("fps must be positive");
+    }
+    d
+}
+
+fn loop_parse(l: &str) -> bool {
+    match l {
+        "once" => false,
+        "forever" => true,
+        _ => panic!("invalid loop"),
+    }
+}
+
 fn origin_parse(origin: &str) -> Origin {
     let origin: Vec<&str> = origin.split('x').collect();
     if origin.len() != 2 {
@@ -140,17 +243,94 @@ fn gradient_parse(g: &str) -> Option<maze::Gradient> {
     }
 }
 
+fn probability_parse(p: &str) -> f64 {
+    let d = f64::from_str(p).expect("algorithm parameter is not a floating number");
+    if !(0.0..=1.0).contains(&d) {
+        panic!("algorithm parameter must be between 0 and 1");
+    }
+    d
+}
+
+fn growing_tree_policy_parse(s: &str) -> maze::GrowingTreePolicy {
+    let mut policy = maze::GrowingTreePolicy {
+        newest: 0,
+        oldest: 0,
+        random: 0,
+        middle: 0,
+    };
+    for selection in s.split(',') {
+        let (name, weight) = match selection.split_once(':') {
+            Some((name, weight)) => (
+                name,
+                u32::from_str(weight).expect("invalid growing tree weight"),
+            ),
+            None => (selection, 1),
+        };
+        match name {
+            "newest" => policy.newest += weight,
+            "oldest" => policy.oldest += weight,
+            "random" => policy.random += weight,
+            "middle" => policy.middle += weight,
+            _ => {
+                panic!("invalid growing tree policy {}", name);
+            }
+        }
+    }
+    if policy.newest + policy.oldest + policy.random + policy.middle == 0 {
+        panic!("growing tree policy {} has no weight", s);
+    }
+    policy
+}
+
 fn algorithm_parse(s: &str) -> maze::AlgorithmKind {
-    match s {
-        "prim" => maze::AlgorithmKind::Prim,
-        "kruskal" => maze::AlgorithmKind::Kruskal,
-        "backtracker" => maze::AlgorithmKind::Backtracker,
+    let (name, parameter) = match s.split_once(':') {
+        Some((name, parameter)) => (name, Some(parameter)),
+        None => (s, None),
+    };
+    match (name, parameter) {
+        ("prim", None) => maze::AlgorithmKind::Prim,
+        ("kruskal", None) => maze::AlgorithmKind::Kruskal,
+        ("backtracker", None) => maze::AlgorithmKind::Backtracker,
+        ("hunt-and-kill", None) => maze::AlgorithmKind::HuntAndKill,
+        ("wilson", None) => maze::AlgorithmKind::Wilson,
+        ("eller", None) => maze::AlgorithmKind::Eller,
+        ("recursive-division", None) => maze::AlgorithmKind::RecursiveDivision,
+        ("binary-tree", None) => maze::AlgorithmKind::BinaryTree { diagonal_bias: 0.5 },
+        ("binary-tree", Some(bias)) => maze::AlgorithmKind::BinaryTree {
+            diagonal_bias: probability_parse(bias),
+        },
+        ("sidewinder", None) => maze::AlgorithmKind::Sidewinder {
+            close_probability: 0.5,
+        },
+        ("sidewinder", Some(probability)) => maze::AlgorithmKind::Sidewinder {
+            close_probability: probability_parse(probability),
+        },
+        ("growing-tree", None) => maze::AlgorithmKind::GrowingTree(maze::GrowingTreePolicy {
+            newest: 1,
+            random: 1,
+            ..Default::default()
+        }),
+        ("growing-tree", Some(policy)) => {
+            maze::AlgorithmKind::GrowingTree(growing_tree_policy_parse(policy))
+        }
+        ("aldous-broder", None) => maze::AlgorithmKind::AldousBroder { wilson_ratio: None },
+        ("aldous-broder", Some(ratio)) => maze::AlgorithmKind::AldousBroder {
+            wilson_ratio: Some(probability_parse(ratio)),
+        },
         _ => {
             panic!("invalid algorithm {}", s);
         }
     }
 }
 
+fn seed_parse(seed: &str) -> Option<u64> {
+    if seed.is_empty() {
+        None
+    } else {
+        Some(u64::from_str(seed).expect("seed is not an unsigned integer"))
+    }
+}
+
 fn colors_parse(bg: &str, fg: &str) -> (Rgb<u8>, [Rgb<u8>; 2]) {
     let bg = color_parse(bg);
 
@@ -170,6 +350,14 @@ fn colors_parse(bg: &str, fg: &str) -> (Rgb<u8>, [Rgb<u8>; 2]) {
     (bg, [fg1, fg2])
 }
 
+fn color_pair_parse(colors: &str) -> [Rgb<u8>; 2] {
+    let vec_str: Vec<&str> = colors.split(' ').collect();
+    if vec_str.len() != 2 {
+        panic!("invalid colors '{}'", colors);
+    }
+    [color_parse(vec_str[0]), color_parse(vec_str[1])]
+}
+
 fn main() {
     let version = env!("CARGO_PKG_VERSION").to_owned();
     let args = Docopt::new(USAGE)
@@ -181,7 +369,13 @@ fn main() {
     let (bg, fg) = colors_parse(args.get_str("--background"), args.get_str("--foreground"));
 
     let rendering = args.get_str("--rendering");
-    let rendering = rendering_parse(rendering, bg, fg);
+    let tile_size = match args.get_str("--tile-size") {
+        "" => None,
+        size => Some(size_parse(size)),
+    };
+    let cell_size = size_parse(args.get_str("--cell-size"));
+    let wall_thickness = size_parse(args.get_str("--wall-thickness"));
+    let rendering = rendering_parse(rendering, bg, fg, tile_size, cell_size, wall_thickness);
 
     let path = args.get_str("FILE");
     let path = path::Path::new(path);
@@ -192,6 +386,11 @@ fn main() {
     let origin = args.get_str("--origin");
     let origin = origin_parse(origin);
 
+    let distance_from = match args.get_str("--distance-from") {
+        "" => None,
+        from => Some(origin_parse(from)),
+    };
+
     let gradient = args.get_str("--gradient");
     let gradient = gradient_parse(gradient);
 
@@ -199,17 +398,171 @@ fn main() {
     let algorithm = algorithm_parse(algorithm);
 
     let animation = args.get_bool("--animation");
+    let frame_every = count_parse(args.get_str("--frame-every"));
+    let max_frames = match args.get_str("--max-frames") {
+        "" => None,
+        max => Some(count_parse(max)),
+    };
+    let fps = fps_parse(args.get_str("--fps"));
+    let final_hold = duration_parse(args.get_str("--final-hold"));
+    let loop_forever = loop_parse(args.get_str("--loop"));
+    let animate_solve = args.get_bool("--animate-solve");
+    let [visited, solution] = color_pair_parse(args.get_str("--solve-colors"));
+    let solution_color = match args.get_str("--show-solution") {
+        "" => None,
+        color => Some(color_parse(color)),
+    };
+    let markers = match args.get_str("--markers") {
+        "" => None,
+        colors => Some(color_pair_parse(colors)),
+    };
+
+    let braid = args.get_str("--braid");
+    let braid = braid_parse(braid);
+
+    let stream = args.get_bool("--stream");
+    if stream {
+        if !matches!(algorithm, maze::AlgorithmKind::Eller) {
+            panic!("streaming requires the eller algorithm");
+        }
+        if animation {
+            panic!("streaming can not be used with an animation");
+        }
+        if path.extension().and_then(|e| e.to_str()) != Some("png") {
+            panic!("streaming requires a PNG file");
+        }
+        if braid > 0_f64 {
+            panic!("a streamed maze can not be braided");
+        }
+        if origin.x != 0_f64 || origin.y != 0_f64 || distance_from.is_some() {
+            panic!("a streamed maze is drawn without distances from an origin");
+        }
+        if solution_color.is_some() || markers.is_some() {
+            panic!("the solution of a streamed maze is not known");
+        }
+    }
+
+    if solution_color.is_some() || markers.is_some() {
+        let is_text = path == path::Path::new("-")
+            || path.extension().and_then(|e| e.to_str()) == Some("txt");
+        if animation || is_text {
+            panic!("the solution and markers are only drawn on images and SVG");
+        }
+    }
 
-    maze::generate_image(
-        path,
-        geometry,
-        &*rendering,
-        vertical_bias,
-        origin,
-        gradient,
-        algorithm,
-        animation,
+    let seed = args.get_str("--seed");
+    let seed = seed_parse(seed);
+
+    let margin =
+        usize::from_str(args.get_str("--margin")).expect("margin is not an unsigned integer");
+    let align = align_parse(args.get_str("--align"));
+
+    let mut builder = MazeBuilder::new()
+        .geometry(geometry)
+        .renderer(rendering)
+        .vertical_bias(vertical_bias)
+        .origin(origin)
+        .algorithm(algorithm)
+        .braid(braid)
+        .ansi(args.get_bool("--ansi"))
+        .margin(margin)
+        .align(align)
+        .frame_every(frame_every)
+        .fps(fps)
+        .final_hold(final_hold)
+        .loop_forever(loop_forever);
+    if let Some(gradient) = gradient {
+        builder = builder.gradient(gradient);
+    }
+    if let Some(seed) = seed {
+        builder = builder.seed(seed);
+    }
+    if let Some(distance_from) = distance_from {
+        builder = builder.distance_from(distance_from);
+    }
+    if let Some(max_frames) = max_frames {
+        builder = builder.max_frames(max_frames);
+    }
+    if animate_solve {
+        builder = builder.animate_solve(visited, solution);
+    }
+    if let Some(color) = solution_color {
+        builder = builder.show_solution(color);
+    }
+    if let Some([start, end]) = markers {
+        builder = builder.markers(start, end);
+    }
+
+    if args.get_bool("solve") {
+        let maze = builder.build();
+        println!(
+            "{:<20} {:>8} {:>8} {:>8} {:>12}",
+            "solver", "path", "steps", "visited", "time"
+        );
+        for kind in solver::SolverKind::ALL {
+            let report = solver::solve(&maze, kind, |_| {});
+            let path = if report.path.is_empty() {
+                "-".to_string()
+            } else {
+                (report.path.len() - 1).to_string()
+            };
+            println!(
+                "{:<20} {:>8} {:>8} {:>8} {:>12?}",
+                kind.name(),
+                path,
+                report.steps,
+                report.visited,
+                report.time
+            );
+        }
+        return;
+    }
+
+    let print_stats = args.get_bool("--stats");
+    let print_stats_json = args.get_bool("--stats-json");
+
+    if stream {
+        if print_stats || print_stats_json {
+            panic!("statistics can not be computed on a streamed maze");
+        }
+        let seed = builder
+            .stream(path)
+            .unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+        eprintln!("seed: {}", seed);
+        return;
+    }
+
+    let maze = if animation {
+        builder.animate(path).map_err(ImageError::from)
+    } else {
+        builder.save(path)
+    };
+    let maze = maze.unwrap_or_else(|e| panic!("cannot write {}: {}", path.display(), e));
+
+    dbg!(
+        "On grid {:?}, from {:?} to {:?} (len: {}). Generated on {} iterations with seed {}",
+        maze.geometry(),
+        maze.origin(),
+        maze.end(),
+        maze.len().ceil(),
+        maze.iterations(),
+        maze.seed()
     );
+
+    if print_stats || print_stats_json {
+        let stats = stats::stats(&maze);
+        /* keep the standard output for the maze when it is written there */
+        let report = if print_stats_json {
+            stats.to_json() + "\n"
+        } else {
+            stats.to_string()
+        };
+        if path == path::Path::new("-") {
+            eprint!("{}", report);
+        } else {
+            print!("{}", report);
+        }
+    }
 }
 
 /* }}} */
@@ -1,10 +1,13 @@
-use gif::{DisposalMethod, Encoder, ExtensionData, Frame, Repeat};
-use image::RgbImage;
-use std::path;
+use gif::{DisposalMethod, Encoder, Frame, Repeat};
+use image::{Rgb, RgbImage};
 
-use rand::{random, Rng};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
 use std::borrow::Cow;
-use std::fs::File;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{self, Write};
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct Coord {
@@ -20,19 +23,46 @@ pub enum CellKind {
     Undefined,
 }
 
+#[derive(Debug, Clone, Copy)]
 pub enum Gradient {
     Length,
     Solution,
 }
 
-#[derive(Debug)]
-enum Direction {
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Direction {
     Up,
     Down,
     Left,
     Right,
 }
-fn opposite(dir: &Direction) -> Direction {
+
+impl Direction {
+    /* Turning right is going to the next one */
+    pub(crate) const CLOCKWISE: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    /* Number of quarter turns to the right from up */
+    pub(crate) fn quarters(self) -> usize {
+        match self {
+            Direction::Up => 0,
+            Direction::Right => 1,
+            Direction::Down => 2,
+            Direction::Left => 3,
+        }
+    }
+
+    /* Direction after `quarters` quarter turns to the right */
+    pub(crate) fn turn(self, quarters: usize) -> Direction {
+        Direction::CLOCKWISE[(self.quarters() + quarters) % 4]
+    }
+}
+
+pub(crate) fn opposite(dir: &Direction) -> Direction {
     match *dir {
         Direction::Up => Direction::Down,
         Direction::Down => Direction::Up,
@@ -51,8 +81,13 @@ fn add_walls(vwalls: &mut Vec<Wall>, hwalls: &mut Vec<Wall>, new_walls: Vec<Coor
     }
 }
 
-fn pop_random_wall(vwalls: &mut Vec<Wall>, hwalls: &mut Vec<Wall>, vertical_bias: f64) -> Wall {
-    let r: usize = rand::rng().random_range(0..usize::MAX);
+fn pop_random_wall(
+    vwalls: &mut Vec<Wall>,
+    hwalls: &mut Vec<Wall>,
+    vertical_bias: f64,
+    rng: &mut StdRng,
+) -> Wall {
+    let r: usize = rng.random_range(0..usize::MAX);
     match (vwalls.len(), hwalls.len()) {
         (0, len) => {
             let pos = r % len;
@@ -63,7 +98,7 @@ fn pop_random_wall(vwalls: &mut Vec<Wall>, hwalls: &mut Vec<Wall>, vertical_bias
             vwalls.swap_remove(pos)
         }
         (vlen, hlen) => {
-            let f = random::<f64>();
+            let f = rng.random::<f64>();
             if f < vertical_bias {
                 let pos = r % vlen;
                 vwalls.swap_remove(pos)
@@ -76,7 +111,7 @@ fn pop_random_wall(vwalls: &mut Vec<Wall>, hwalls: &mut Vec<Wall>, vertical_bias
 }
 
 pub trait Algorithm<'a> {
-    fn next(&mut self) -> Option<&Maze>;
+    fn next(&mut self) -> Option<&mut Maze>;
 }
 
 /* Kruskal {{{ */
@@ -145,11 +180,10 @@ impl<'a> Kruskal<'a> {
         };
         stack.push(c.clone());
         loop {
-            self.maze.grid[c.y * self.maze.geometry.width + c.x] = CellKind::PathKind(f);
+            self.maze.set_cell(&c, CellKind::PathKind(f));
             match self.maze.walk(&c, is_visited) {
                 Some((next, _)) => {
-                    self.maze.grid[next.y * self.maze.geometry.width + next.x] =
-                        CellKind::PathKind(f);
+                    self.maze.set_cell(&next, CellKind::PathKind(f));
                     stack.push(c);
                     c = next;
                 }
@@ -164,63 +198,21 @@ impl<'a> Kruskal<'a> {
             }
         }
     }
-
-    fn find_end(&mut self) {
-        self.maze.clear_path();
-        let mut stack: Vec<(Coord, Direction, f64)> = Vec::new();
-        let mut c = self.maze.origin.clone();
-        let mut f = 0_f64;
-        let is_visited = |m: &Maze, c: &Coord| m.is_visited(c);
-        self.maze.set_path(&c, 0_f64);
-        loop {
-            match self.maze.walk(&c, is_visited) {
-                Some((next, direction)) => {
-                    self.maze.set_path(&next, 0_f64);
-                    f += 1_f64;
-                    if f > self.maze.len {
-                        self.maze.len = f;
-                        self.maze.end = next.clone();
-                    }
-                    stack.push((c, direction, f));
-                    c = next;
-                }
-                None => {
-                    if let Some((next, _, distance)) = stack.pop() {
-                        c = next;
-                        f = distance;
-                    } else {
-                        break;
-                    }
-                }
-            }
-        }
-    }
 }
 
 impl<'a> Algorithm<'a> for Kruskal<'a> {
-    fn next(&mut self) -> Option<&Maze> {
+    fn next(&mut self) -> Option<&mut Maze> {
         if self.vwalls.is_empty() || self.hwalls.is_empty() {
-            /* Find end */
-            self.find_end();
-            /* mark unvisited as walls */
-            for y in 0..self.maze.geometry.height {
-                for x in 0..self.maze.geometry.width {
-                    let c = Coord { x, y };
-                    match self.maze.cell_kind(&c) {
-                        CellKind::Undefined => {
-                            self.maze.set_wall(&c);
-                        }
-                        CellKind::PathKind(f) => {
-                            self.maze.grid[y * self.maze.geometry.width + x] =
-                                CellKind::PathKind(f / self.maze.len);
-                        }
-                        _ => {}
-                    }
-                }
-            }
+            /* path values are set ids, replace them with distances */
+            self.maze.finish();
             return None;
         }
-        let w = pop_random_wall(&mut self.vwalls, &mut self.hwalls, self.maze.vertical_bias);
+        let w = pop_random_wall(
+            &mut self.vwalls,
+            &mut self.hwalls,
+            self.maze.vertical_bias,
+            &mut self.maze.rng,
+        );
         if let Some(dir) = self.maze.get_random_wall_direction(&w) {
             let o1 = self.maze.get_coord_next(&w as &Coord, &dir);
             let o2 = self.maze.get_coord_next(&w as &Coord, &opposite(&dir));
@@ -350,27 +342,32 @@ impl<'a> Prim<'a> {
 }
 
 impl<'a> Algorithm<'a> for Prim<'a> {
-    fn next(&mut self) -> Option<&Maze> {
+    fn next(&mut self) -> Option<&mut Maze> {
         if self.vwalls.is_empty() && self.hwalls.is_empty() {
             for y in (0..self.maze.geometry.height).filter(|&v| v % 2 == 1) {
                 for x in (0..self.maze.geometry.width).filter(|&v| v % 2 == 1) {
                     if let CellKind::Undefined = self.maze.cell_kind(&Coord { x, y }) {
-                        self.maze.grid[y * self.maze.geometry.width + x] = CellKind::WallKind;
+                        self.maze.set_cell(&Coord { x, y }, CellKind::WallKind);
                     }
                 }
             }
             for y in 0..self.maze.geometry.height {
                 for x in 0..self.maze.geometry.width {
                     if let CellKind::PathKind(f) = self.maze.cell_kind(&Coord { x, y }) {
-                        self.maze.grid[y * self.maze.geometry.width + x] =
-                            CellKind::PathKind(f / self.maze.len);
+                        self.maze
+                            .set_cell(&Coord { x, y }, CellKind::PathKind(f / self.maze.len));
                     }
                 }
             }
             return None;
         }
         /* Pick a random wall from the list */
-        let w = pop_random_wall(&mut self.vwalls, &mut self.hwalls, self.maze.vertical_bias);
+        let w = pop_random_wall(
+            &mut self.vwalls,
+            &mut self.hwalls,
+            self.maze.vertical_bias,
+            &mut self.maze.rng,
+        );
         if let Some(dir) = self.maze.get_random_wall_direction(&w) {
             let o1 = self.maze.get_coord_next(&w as &Coord, &dir);
             let o2 = self.maze.get_coord_next(&w as &Coord, &opposite(&dir));
@@ -485,27 +482,6 @@ impl<'a> Backtracker<'a> {
         }
     }
 
-    fn get_coord_twice(&self, c: &Coord, dir: &Direction) -> Option<Coord> {
-        match *dir {
-            Direction::Up => self
-                .maze
-                .get_coord_up(c)
-                .and_then(|n| self.maze.get_coord_up(&n)),
-            Direction::Down => self
-                .maze
-                .get_coord_down(c)
-                .and_then(|n| self.maze.get_coord_down(&n)),
-            Direction::Left => self
-                .maze
-                .get_coord_left(c)
-                .and_then(|n| self.maze.get_coord_left(&n)),
-            Direction::Right => self
-                .maze
-                .get_coord_right(c)
-                .and_then(|n| self.maze.get_coord_right(&n)),
-        }
-    }
-
     fn get_random_unvisited_cell_neighbour(&mut self) -> Option<Coord> {
         let dirs = vec![
             Direction::Up,
@@ -515,7 +491,7 @@ impl<'a> Backtracker<'a> {
         ];
         let mut vec: Vec<Coord> = Vec::with_capacity(4);
         for d in dirs {
-            if let Some(n) = self.get_coord_twice(&self.c, &d) {
+            if let Some(n) = self.maze.get_coord_twice(&self.c, &d) {
                 if let CellKind::Undefined = self.maze.cell_kind(&n) {
                     vec.push(n);
                 }
@@ -524,7 +500,7 @@ impl<'a> Backtracker<'a> {
         if vec.is_empty() {
             None
         } else {
-            let r: usize = rand::rng().random_range(0..usize::MAX);
+            let r: usize = self.maze.rng.random_range(0..usize::MAX);
             let len = vec.len();
             Some(vec.swap_remove(r % len))
         }
@@ -532,7 +508,7 @@ impl<'a> Backtracker<'a> {
 }
 
 impl<'a> Algorithm<'a> for Backtracker<'a> {
-    fn next(&mut self) -> Option<&Maze> {
+    fn next(&mut self) -> Option<&mut Maze> {
         if self.to_finish {
             /* mark unvisited as walls */
             for y in 0..self.maze.geometry.height {
@@ -543,8 +519,8 @@ impl<'a> Algorithm<'a> for Backtracker<'a> {
                             self.maze.set_wall(&c);
                         }
                         CellKind::PathKind(f) => {
-                            self.maze.grid[y * self.maze.geometry.width + x] =
-                                CellKind::PathKind(f / self.maze.len);
+                            self.maze
+                                .set_cell(&c, CellKind::PathKind(f / self.maze.len));
                         }
                         _ => {}
                     }
@@ -589,209 +565,1349 @@ impl<'a> Algorithm<'a> for Backtracker<'a> {
 }
 
 /* }}} */
-
-pub enum AlgorithmKind {
-    Prim,
-    Kruskal,
-    Backtracker,
+/* Hunt-and-Kill {{{ */
+/*
+ * 1. Make the initial cell the current cell and mark it as visited.
+ * 2. Kill: while the current cell has unvisited neighbours, remove the wall
+ *    to a random one of them and make it the current cell.
+ * 3. Hunt: scan the grid for an unvisited cell next to a visited one.
+ *    Remove the wall between them, make it the current cell and go back to
+ *    step 2. When there is no such cell, the maze is complete.
+ *
+ * It gives long winding passages like the recursive backtracker, without
+ * any stack. Rows are scanned from the first one that still has unvisited
+ * cells.
+ */
+struct HuntAndKill<'a> {
+    maze: &'a mut Maze,
+    c: Coord,
+    /* rows above this one have no unvisited cells */
+    hunt_y: usize,
 }
 
-/* Maze {{{ */
-#[derive(Debug, Clone)]
-pub struct Maze {
-    geometry: super::Geometry,
-    grid: Vec<CellKind>,
-    vertical_bias: f64,
-    origin: Coord,
-    end: Coord,
-    len: f64,
-}
+impl<'a> HuntAndKill<'a> {
+    fn init(maze: &'a mut Maze) -> HuntAndKill<'a> {
+        let c = maze.origin();
+        maze.set_path(&c, 0_f64);
+        maze.len = 0_f64;
+        HuntAndKill { maze, c, hunt_y: 0 }
+    }
 
-impl Maze {
-    fn new(g: &super::Geometry, vertical_bias: f64, origin: &super::Origin) -> Maze {
-        let mut m = Maze {
-            geometry: g.clone(),
-            grid: Vec::new(),
-            vertical_bias,
-            origin: Coord { x: 0, y: 0 },
-            len: 0_f64,
-            end: Coord {
-                x: g.width - 1,
-                y: g.height - 1,
-            },
+    /* Connect the unvisited cell `n` to the visited cell `c` */
+    fn carve(&mut self, c: &Coord, n: &Coord) {
+        let d = match self.maze.cell_kind(c) {
+            CellKind::PathKind(d) => d,
+            _ => 0_f64,
         };
-        m.grid.reserve(g.width * g.height);
-        for _ in 0..(g.width * g.height) {
-            m.grid.push(CellKind::Undefined);
+        let w = Coord {
+            x: (n.x + c.x) / 2,
+            y: (n.y + c.y) / 2,
+        };
+        self.maze.set_path(&w, d + 1_f64);
+        self.maze.set_path(n, d + 2_f64);
+        if self.maze.len < d + 2_f64 {
+            self.maze.len = d + 2_f64;
+            self.maze.end = n.clone();
         }
-        m.origin = m.origin_to_coord(origin);
-        m
+        self.c = n.clone();
     }
 
-    pub fn origin(&self) -> Coord {
-        self.origin.clone()
-    }
-    pub fn end(&self) -> Coord {
-        self.end.clone()
-    }
-    pub fn len(&self) -> f64 {
-        self.len
+    fn pick_random(&mut self, mut v: Vec<Coord>) -> Option<Coord> {
+        if v.is_empty() {
+            None
+        } else {
+            let r: usize = self.maze.rng.random_range(0..v.len());
+            Some(v.swap_remove(r))
+        }
     }
 
-    fn origin_to_coord(&self, origin: &super::Origin) -> Coord {
-        let x = origin.x * (self.geometry.width as f64);
-        let y = origin.y * (self.geometry.height as f64);
-        Coord {
-            x: (x as usize) & !1,
-            y: (y as usize) & !1,
+    fn hunt(&mut self) -> Option<(Coord, Coord)> {
+        for y in (self.hunt_y..self.maze.geometry.height).step_by(2) {
+            let mut is_complete = true;
+            for x in (0..self.maze.geometry.width).step_by(2) {
+                let n = Coord { x, y };
+                if let CellKind::Undefined = self.maze.cell_kind(&n) {
+                    is_complete = false;
+                    let visited: Vec<Coord> = self
+                        .maze
+                        .get_cells_around(&n)
+                        .into_iter()
+                        .filter(|c| matches!(self.maze.cell_kind(c), CellKind::PathKind(_)))
+                        .collect();
+                    if let Some(c) = self.pick_random(visited) {
+                        return Some((c, n));
+                    }
+                }
+            }
+            if is_complete && y == self.hunt_y {
+                self.hunt_y += 2;
+            }
         }
+        None
     }
+}
 
-    fn cell_kind(&self, c: &Coord) -> CellKind {
-        if c.x >= self.geometry.width || c.y >= self.geometry.height {
-            CellKind::Undefined
-        } else {
-            self.grid[c.y * self.geometry.width + c.x].clone()
+impl<'a> Algorithm<'a> for HuntAndKill<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        let c = self.c.clone();
+        let unvisited: Vec<Coord> = self
+            .maze
+            .get_cells_around(&c)
+            .into_iter()
+            .filter(|n| matches!(self.maze.cell_kind(n), CellKind::Undefined))
+            .collect();
+        if let Some(n) = self.pick_random(unvisited) {
+            self.carve(&c, &n);
+            return Some(self.maze);
+        }
+        if let Some((c, n)) = self.hunt() {
+            self.carve(&c, &n);
+            return Some(self.maze);
+        }
+        /* mark unvisited as walls */
+        let len = self.maze.len.max(1_f64);
+        for y in 0..self.maze.geometry.height {
+            for x in 0..self.maze.geometry.width {
+                let c = Coord { x, y };
+                match self.maze.cell_kind(&c) {
+                    CellKind::Undefined => {
+                        self.maze.set_wall(&c);
+                    }
+                    CellKind::PathKind(f) => {
+                        self.maze.set_cell(&c, CellKind::PathKind(f / len));
+                    }
+                    _ => {}
+                }
+            }
         }
+        None
     }
+}
 
-    fn set_path(&mut self, c: &Coord, d: f64) {
-        self.grid[c.y * self.geometry.width + c.x] = CellKind::PathKind(d);
-    }
+/* }}} */
+/* Wilson {{{ */
+/*
+ * Loop-erased random walks, producing a uniform spanning tree: every
+ * possible maze is equally likely, without any bias.
+ *
+ * 1. Add the initial cell to the maze.
+ * 2. While there are cells not in the maze:
+ *    1. Start a random walk from a random cell not in the maze.
+ *    2. Walk randomly until reaching a cell in the maze. Whenever the walk
+ *       crosses itself, erase the loop it just made.
+ *    3. Add the cells of the walk, and the walls between them, to the maze.
+ *
+ * Cells in the maze have a path value of 0 and cells of the current walk a
+ * path value of 1 until the maze is complete.
+ */
+const WILSON_IN_MAZE: f64 = 0_f64;
+const WILSON_IN_WALK: f64 = 1_f64;
 
-    fn set_wall(&mut self, c: &Coord) {
-        if let CellKind::WallKind = self.cell_kind(c) {
-            return;
-        }
-        self.grid[c.y * self.geometry.width + c.x] = CellKind::WallKind;
-    }
+struct Wilson<'a> {
+    maze: &'a mut Maze,
+    /* cells to start walks from, in random order */
+    cells: Vec<Coord>,
+    walk: Vec<Coord>,
+    /* position in the walk of every cell of the grid */
+    walk_index: Vec<Option<usize>>,
+}
 
-    fn get_coord_up(&self, c: &Coord) -> Option<Coord> {
-        if c.y == 0 {
-            None
-        } else {
-            Some(Coord { x: c.x, y: c.y - 1 })
+impl<'a> Wilson<'a> {
+    fn init(maze: &'a mut Maze) -> Wilson<'a> {
+        let origin = maze.origin();
+        maze.set_path(&origin, WILSON_IN_MAZE);
+        let mut cells = maze.cells();
+        cells.shuffle(&mut maze.rng);
+        let walk_index = vec![None; maze.grid.len()];
+        Wilson {
+            maze,
+            cells,
+            walk: Vec::new(),
+            walk_index,
         }
     }
-    fn get_coord_down(&self, c: &Coord) -> Option<Coord> {
-        if c.y >= self.geometry.height - 1 {
-            None
-        } else {
-            Some(Coord { x: c.x, y: c.y + 1 })
-        }
+
+    fn index(&self, c: &Coord) -> usize {
+        c.y * self.maze.geometry.width + c.x
     }
-    fn get_coord_left(&self, c: &Coord) -> Option<Coord> {
-        if c.x == 0 {
-            None
-        } else {
-            Some(Coord { x: c.x - 1, y: c.y })
+
+    fn push(&mut self, c: Coord) {
+        if let Some(last) = self.walk.last() {
+            let w = Coord {
+                x: (last.x + c.x) / 2,
+                y: (last.y + c.y) / 2,
+            };
+            self.maze.set_path(&w, WILSON_IN_WALK);
         }
+        self.maze.set_path(&c, WILSON_IN_WALK);
+        let idx = self.index(&c);
+        self.walk_index[idx] = Some(self.walk.len());
+        self.walk.push(c);
     }
-    fn get_coord_right(&self, c: &Coord) -> Option<Coord> {
-        if c.x >= self.geometry.width - 1 {
-            None
-        } else {
-            Some(Coord { x: c.x + 1, y: c.y })
+
+    /* Erase the loop made by the walk, keeping it up to position `pos` */
+    fn erase_loop(&mut self, pos: usize) {
+        while self.walk.len() > pos + 1 {
+            let c = self.walk.pop().unwrap();
+            let last = self.walk.last().unwrap();
+            let w = Coord {
+                x: (last.x + c.x) / 2,
+                y: (last.y + c.y) / 2,
+            };
+            let idx = self.index(&c);
+            self.walk_index[idx] = None;
+            self.maze.set_cell(&c, CellKind::Undefined);
+            self.maze.set_cell(&w, CellKind::Undefined);
         }
     }
 
-    fn get_coord_next(&self, c: &Coord, dir: &Direction) -> Option<Coord> {
-        match *dir {
-            Direction::Up => self.get_coord_up(c),
-            Direction::Down => self.get_coord_down(c),
-            Direction::Left => self.get_coord_left(c),
-            Direction::Right => self.get_coord_right(c),
+    /* Add the walk to the maze, connecting it to the cell `c` */
+    fn add_walk(&mut self, c: &Coord) {
+        let mut previous = c.clone();
+        while let Some(n) = self.walk.pop() {
+            let w = Coord {
+                x: (previous.x + n.x) / 2,
+                y: (previous.y + n.y) / 2,
+            };
+            self.maze.set_path(&w, WILSON_IN_MAZE);
+            self.maze.set_path(&n, WILSON_IN_MAZE);
+            let idx = self.index(&n);
+            self.walk_index[idx] = None;
+            previous = n;
         }
     }
+}
 
-    fn get_random_wall_direction(&self, w: &Wall) -> Option<Direction> {
-        match (w.x % 2, w.y % 2) {
-            (0, 1) => match random::<u8>() % 2 {
-                0 => Some(Direction::Up),
-                _ => Some(Direction::Down),
-            },
-            (1, 0) => match random::<u8>() % 2 {
-                0 => Some(Direction::Left),
-                _ => Some(Direction::Right),
-            },
-            (1, 1) => {
-                let f = random::<f64>();
-                if f < self.vertical_bias {
-                    match random::<u8>() % 2 {
-                        0 => Some(Direction::Left),
-                        _ => Some(Direction::Right),
-                    }
-                } else {
-                    match random::<u8>() % 2 {
-                        0 => Some(Direction::Up),
-                        _ => Some(Direction::Down),
+impl<'a> Algorithm<'a> for Wilson<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        let c = match self.walk.last() {
+            Some(c) => c.clone(),
+            None => {
+                /* Start a new walk */
+                while let Some(c) = self.cells.pop() {
+                    if let CellKind::Undefined = self.maze.cell_kind(&c) {
+                        self.push(c);
+                        return Some(self.maze);
                     }
                 }
+                self.maze.finish();
+                return None;
             }
-            (_, _) => {
-                /* (0, 0) */
-                None
+        };
+        let mut neighbours = self.maze.get_cells_around(&c);
+        if neighbours.is_empty() {
+            /* Lone cell that can not be connected to anything */
+            self.walk.clear();
+            let idx = self.index(&c);
+            self.walk_index[idx] = None;
+            self.maze.set_cell(&c, CellKind::Undefined);
+            return Some(self.maze);
+        }
+        let r: usize = self.maze.rng.random_range(0..neighbours.len());
+        let n = neighbours.swap_remove(r);
+        match self.walk_index[self.index(&n)] {
+            Some(pos) => self.erase_loop(pos),
+            None => {
+                if let CellKind::PathKind(_) = self.maze.cell_kind(&n) {
+                    self.add_walk(&n);
+                } else {
+                    self.push(n);
+                }
             }
         }
+        Some(self.maze)
     }
+}
 
-    fn draw<T: ?Sized + Rendering>(&self, renderer: &T) -> RgbImage {
-        let g = image_geometry(renderer, &self.geometry);
-        let mut img = RgbImage::new(g.width as u32, g.height as u32);
+/* }}} */
+/* Aldous-Broder {{{ */
+/*
+ * 1. Pick the initial cell as the current cell and mark it as visited.
+ * 2. While there are unvisited cells:
+ *    1. Pick a random neighbour.
+ *    2. If the neighbour has not been visited, remove the wall between the
+ *       current cell and the neighbour and mark the neighbour as visited.
+ *    3. Make the neighbour the current cell.
+ *
+ * Like Wilson's algorithm, it produces uniform spanning trees, but it takes
+ * a long time to find the last unvisited cells. The hybrid version switches
+ * to Wilson's algorithm once a given fraction of the cells is visited:
+ * Wilson's algorithm is slow at the beginning, when the maze is small, and
+ * fast once the maze covers a large part of the grid.
+ */
+struct AldousBroder<'a> {
+    maze: &'a mut Maze,
+    c: Coord,
+    nb_unvisited: usize,
+}
 
-        for y in 0..self.geometry.height {
-            for x in 0..self.geometry.width {
-                let c = Coord { x, y };
-                renderer.draw_cell(self, &mut img, &c, self.cell_kind(&c));
+impl<'a> AldousBroder<'a> {
+    fn init(maze: &'a mut Maze) -> AldousBroder<'a> {
+        let c = maze.origin();
+        maze.set_path(&c, 0_f64);
+        let nb_unvisited = maze.cells().len() - 1;
+        AldousBroder {
+            maze,
+            c,
+            nb_unvisited,
+        }
+    }
+}
+
+impl<'a> Algorithm<'a> for AldousBroder<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        while self.nb_unvisited > 0 {
+            let mut neighbours = self.maze.get_cells_around(&self.c);
+            if neighbours.is_empty() {
+                break;
+            }
+            let r: usize = self.maze.rng.random_range(0..neighbours.len());
+            let n = neighbours.swap_remove(r);
+            let carved = if let CellKind::Undefined = self.maze.cell_kind(&n) {
+                let w = Coord {
+                    x: (n.x + self.c.x) / 2,
+                    y: (n.y + self.c.y) / 2,
+                };
+                self.maze.set_path(&w, 0_f64);
+                self.maze.set_path(&n, 0_f64);
+                self.nb_unvisited -= 1;
+                true
+            } else {
+                false
+            };
+            self.c = n;
+            if carved {
+                return Some(self.maze);
             }
         }
-        img
+        self.maze.finish();
+        None
     }
-    fn draw_gif<T: ?Sized + Rendering>(&self, renderer: &T) -> Frame<'_> {
-        let g = image_geometry(renderer, &self.geometry);
+}
 
-        let mut buffer: Vec<u8> = vec![0; g.width * g.height];
+enum AldousBroderWilson<'a> {
+    AldousBroder {
+        ab: AldousBroder<'a>,
+        /* switch to Wilson's algorithm when there are that many unvisited
+         * cells left */
+        nb_switch: usize,
+    },
+    Wilson(Wilson<'a>),
+    Switching,
+}
 
-        for y in 0..self.geometry.height {
-            for x in 0..self.geometry.width {
-                let c = Coord { x, y };
-                renderer.draw_cell_gif(self, &g, &mut buffer, &c, self.cell_kind(&c));
+impl<'a> AldousBroderWilson<'a> {
+    fn init(maze: &'a mut Maze, ratio: f64) -> AldousBroderWilson<'a> {
+        let nb_cells = maze.cells().len() as f64;
+        let nb_switch = (nb_cells * (1_f64 - ratio)).floor() as usize;
+        AldousBroderWilson::AldousBroder {
+            ab: AldousBroder::init(maze),
+            nb_switch,
+        }
+    }
+}
+
+impl<'a> Algorithm<'a> for AldousBroderWilson<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        let switch = match self {
+            AldousBroderWilson::AldousBroder { ab, nb_switch } => ab.nb_unvisited <= *nb_switch,
+            _ => false,
+        };
+        if switch {
+            if let AldousBroderWilson::AldousBroder { ab, .. } =
+                std::mem::replace(self, AldousBroderWilson::Switching)
+            {
+                *self = AldousBroderWilson::Wilson(Wilson::init(ab.maze));
             }
         }
+        match self {
+            AldousBroderWilson::AldousBroder { ab, .. } => ab.next(),
+            AldousBroderWilson::Wilson(w) => w.next(),
+            AldousBroderWilson::Switching => None,
+        }
+    }
+}
 
-        Frame {
-            width: g.width as u16,
-            height: g.height as u16,
-            palette: None,
-            transparent: None,
-            buffer: Cow::Owned(buffer),
-            ..Default::default()
+/* }}} */
+/* Eller {{{ */
+/*
+ * Generates the maze row by row, only keeping the state of the current row:
+ * 1. Put every cell of the first row in its own set.
+ * 2. Randomly join adjacent cells that are not in the same set, merging
+ *    their sets.
+ * 3. For each set, randomly carve downwards, at least once per set. The
+ *    cells below join the set of the cell they come from.
+ * 4. Put the other cells of the next row in their own set and repeat from
+ *    step 2.
+ * 5. On the last row, join all adjacent cells that are not in the same set.
+ *
+ * The vertical bias is the probability to carve downwards, its complement
+ * the probability to join adjacent cells.
+ */
+struct EllerRow {
+    sets: Vec<usize>,
+    next_set: usize,
+}
+
+impl EllerRow {
+    fn new(nb_cells: usize) -> EllerRow {
+        EllerRow {
+            sets: (0..nb_cells).collect(),
+            next_set: nb_cells,
         }
     }
 
-    fn clear_path(&mut self) {
-        for y in 0..self.geometry.height {
-            for x in 0..self.geometry.width {
-                if let CellKind::PathKind(_) = self.cell_kind(&Coord { x, y }) {
-                    self.grid[y * self.geometry.width + x] = CellKind::PathKind(-1_f64);
+    /* Returns whether each cell is joined to the cell on its right */
+    fn join(&mut self, rng: &mut StdRng, probability: f64, is_last: bool) -> Vec<bool> {
+        let mut joined = vec![false; self.sets.len()];
+        for i in 1..self.sets.len() {
+            let (s1, s2) = (self.sets[i - 1], self.sets[i]);
+            if s1 != s2 && (is_last || rng.random::<f64>() < probability) {
+                joined[i - 1] = true;
+                for s in self.sets.iter_mut().filter(|s| **s == s2) {
+                    *s = s1;
                 }
             }
         }
+        joined
     }
 
-    fn is_visited(&self, c: &Coord) -> Option<bool> {
-        if let CellKind::PathKind(f) = self.cell_kind(c) {
-            if f == -1_f64 {
-                Some(false)
-            } else {
-                Some(true)
+    /* Returns whether each cell is joined to the cell below it, and moves
+     * the sets to the next row */
+    fn carve_down(&mut self, rng: &mut StdRng, probability: f64) -> Vec<bool> {
+        let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for (i, s) in self.sets.iter().enumerate() {
+            members.entry(*s).or_default().push(i);
+        }
+        let mut down = vec![false; self.sets.len()];
+        for cells in members.values() {
+            let mut carved = false;
+            for &i in cells {
+                if rng.random::<f64>() < probability {
+                    down[i] = true;
+                    carved = true;
+                }
+            }
+            if !carved {
+                down[cells[rng.random_range(0..cells.len())]] = true;
             }
+        }
+        for (s, d) in self.sets.iter_mut().zip(&down) {
+            if !d {
+                *s = self.next_set;
+                self.next_set += 1;
+            }
+        }
+        down
+    }
+}
+
+/* Set the row of cells `y` and the row of walls below it */
+fn set_eller_rows(maze: &mut Maze, y: usize, joined: &[bool], down: Option<&[bool]>) {
+    for x in 0..maze.geometry.width {
+        let c = Coord { x, y };
+        if x % 2 == 0 || joined[x / 2] {
+            maze.set_path(&c, 0_f64);
         } else {
-            None
+            maze.set_wall(&c);
+        }
+    }
+    if y + 1 >= maze.geometry.height {
+        return;
+    }
+    for x in 0..maze.geometry.width {
+        let c = Coord { x, y: y + 1 };
+        match down {
+            Some(down) if x % 2 == 0 && down[x / 2] => maze.set_path(&c, 0_f64),
+            _ => maze.set_wall(&c),
         }
     }
+}
 
-    fn walk<F>(&self, start: &Coord, is_visited: F) -> Option<(Coord, Direction)>
+struct Eller<'a> {
+    maze: &'a mut Maze,
+    row: EllerRow,
+    y: usize,
+}
+
+impl<'a> Eller<'a> {
+    fn init(maze: &'a mut Maze) -> Eller<'a> {
+        let row = EllerRow::new(maze.geometry.width.div_ceil(2));
+        Eller { maze, row, y: 0 }
+    }
+}
+
+impl<'a> Algorithm<'a> for Eller<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        if self.y >= self.maze.geometry.height {
+            self.maze.finish();
+            return None;
+        }
+        let is_last = self.y + 2 >= self.maze.geometry.height;
+        let vertical_bias = self.maze.vertical_bias;
+        let joined = self
+            .row
+            .join(&mut self.maze.rng, 1_f64 - vertical_bias, is_last);
+        let down = if is_last {
+            None
+        } else {
+            Some(self.row.carve_down(&mut self.maze.rng, vertical_bias))
+        };
+        set_eller_rows(self.maze, self.y, &joined, down.as_deref());
+        self.y += 2;
+        Some(self.maze)
+    }
+}
+
+/* Generate a maze with Eller's algorithm and write it as a PNG image, row
+ * after row, without keeping the whole maze in memory. The paths are drawn
+ * without gradient since distances are not known. */
+pub(crate) fn generate_streaming<T: ?Sized + Rendering, W: Write>(
+    g: &super::Geometry,
+    renderer: &T,
+    vertical_bias: f64,
+    seed: u64,
+    layout: &super::Layout,
+    w: W,
+) -> io::Result<()> {
+    let img_geom = image_geometry(renderer, g);
+    let mut encoder = png::Encoder::new(
+        w,
+        layout.geometry.width as u32,
+        layout.geometry.height as u32,
+    );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut png_writer = encoder.write_header()?;
+    let mut writer = png_writer.stream_writer()?;
+
+    /* Pad the maze with the background color to the size of the image */
+    let background: Vec<u8> = renderer
+        .cell_color(&CellKind::WallKind)
+        .0
+        .repeat(layout.geometry.width);
+    let left = &background[..layout.left * 3];
+    let right = &background[(layout.left + img_geom.width) * 3..];
+    for _ in 0..layout.top {
+        writer.write_all(&background)?;
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut row = EllerRow::new(g.width.div_ceil(2));
+    let origin = super::Origin { x: 0_f64, y: 0_f64 };
+    for y in (0..g.height).step_by(2) {
+        let is_last = y + 2 >= g.height;
+        let strip_geometry = super::Geometry {
+            width: g.width,
+            height: (g.height - y).min(2),
+        };
+        /* Renderers draw the cells of the strip as those of the whole maze */
+        let mut strip = Maze::new(&strip_geometry, vertical_bias, &origin, seed);
+        strip.row_offset = y;
+        let joined = row.join(&mut rng, 1_f64 - vertical_bias, is_last);
+        let down = if is_last {
+            None
+        } else {
+            Some(row.carve_down(&mut rng, vertical_bias))
+        };
+        set_eller_rows(&mut strip, 0, &joined, down.as_deref());
+        /* Renderers may draw a border before the first row of the strip,
+         * only keep it on the first strip */
+        let img = strip.draw(renderer);
+        let skip = if y == 0 { 0 } else { renderer.tile_offset(0) };
+        for line in img.as_raw()[skip * img_geom.width * 3..].chunks(img_geom.width * 3) {
+            writer.write_all(left)?;
+            writer.write_all(line)?;
+            writer.write_all(right)?;
+        }
+    }
+    let bottom = layout.geometry.height - layout.top - img_geom.height;
+    for _ in 0..bottom {
+        writer.write_all(&background)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/* }}} */
+/* Growing Tree {{{ */
+/*
+ * 1. Add the initial cell to the list of active cells.
+ * 2. While the list is not empty:
+ *    1. Pick a cell from the list, according to the selection policy.
+ *    2. If it has unvisited neighbours, remove the wall between the cell
+ *       and a random one of them and add that neighbour to the list.
+ *    3. Otherwise, remove the cell from the list.
+ *
+ * Always picking the newest cell behaves like the recursive backtracker,
+ * producing long rivers. Always picking a random cell behaves like Prim's
+ * algorithm, producing many short branches. Mixing policies gives textures
+ * in between.
+ */
+
+/// Weights of the ways the Growing Tree algorithm picks the cell to grow the
+/// maze from. Each step picks the newest, oldest, a random or the middle
+/// cell of the list of active cells with a probability proportional to
+/// these weights. A policy without any weight picks the newest cell, like
+/// the default one.
+#[derive(Debug, Clone, Copy)]
+pub struct GrowingTreePolicy {
+    pub newest: u32,
+    pub oldest: u32,
+    pub random: u32,
+    pub middle: u32,
+}
+
+impl Default for GrowingTreePolicy {
+    fn default() -> Self {
+        GrowingTreePolicy {
+            newest: 1,
+            oldest: 0,
+            random: 0,
+            middle: 0,
+        }
+    }
+}
+
+impl GrowingTreePolicy {
+    fn total(&self) -> u32 {
+        self.newest + self.oldest + self.random + self.middle
+    }
+
+    fn pick(&self, rng: &mut StdRng, len: usize) -> usize {
+        let mut w = rng.random_range(0..self.total());
+        if w < self.newest {
+            return len - 1;
+        }
+        w -= self.newest;
+        if w < self.oldest {
+            return 0;
+        }
+        w -= self.oldest;
+        if w < self.random {
+            return rng.random_range(0..len);
+        }
+        len / 2
+    }
+}
+
+struct GrowingTree<'a> {
+    maze: &'a mut Maze,
+    policy: GrowingTreePolicy,
+    active: VecDeque<Coord>,
+}
+
+impl<'a> GrowingTree<'a> {
+    fn init(maze: &'a mut Maze, policy: GrowingTreePolicy) -> GrowingTree<'a> {
+        let policy = if policy.total() == 0 {
+            GrowingTreePolicy::default()
+        } else {
+            policy
+        };
+        let c = maze.origin();
+        maze.set_path(&c, 0_f64);
+        let mut active = VecDeque::new();
+        active.push_back(c);
+        GrowingTree {
+            maze,
+            policy,
+            active,
+        }
+    }
+}
+
+impl<'a> Algorithm<'a> for GrowingTree<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        while !self.active.is_empty() {
+            let idx = self.policy.pick(&mut self.maze.rng, self.active.len());
+            let c = self.active[idx].clone();
+            let mut neighbours: Vec<Coord> = self
+                .maze
+                .get_cells_around(&c)
+                .into_iter()
+                .filter(|n| matches!(self.maze.cell_kind(n), CellKind::Undefined))
+                .collect();
+            if neighbours.is_empty() {
+                self.active.remove(idx);
+                continue;
+            }
+            let r: usize = self.maze.rng.random_range(0..neighbours.len());
+            let n = neighbours.swap_remove(r);
+            let w = Coord {
+                x: (n.x + c.x) / 2,
+                y: (n.y + c.y) / 2,
+            };
+            self.maze.set_path(&w, 0_f64);
+            self.maze.set_path(&n, 0_f64);
+            self.active.push_back(n);
+            return Some(self.maze);
+        }
+        self.maze.finish();
+        None
+    }
+}
+
+/* }}} */
+/* Recursive Division {{{ */
+/*
+ * Unlike the other algorithms, walls are added to an open field instead of
+ * passages being carved:
+ * 1. Start with a grid with no walls between the cells: a single chamber.
+ * 2. While there are chambers larger than a cell:
+ *    1. Pick a chamber and split it in two with a vertical or horizontal
+ *       wall, at a random position.
+ *    2. Open a single passage at a random position in that wall.
+ *
+ * The orientation of the wall is picked at random, with the vertical bias:
+ * a vertical wall lets the passages on each side run vertically. It
+ * produces long straight corridors.
+ */
+
+/* Chamber, in cells */
+struct Chamber {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+}
+
+struct RecursiveDivision<'a> {
+    maze: &'a mut Maze,
+    chambers: Vec<Chamber>,
+}
+
+impl<'a> RecursiveDivision<'a> {
+    fn init(maze: &'a mut Maze) -> RecursiveDivision<'a> {
+        let (width, height) = (maze.geometry.width, maze.geometry.height);
+        for y in 0..height {
+            for x in 0..width {
+                let c = Coord { x, y };
+                match (x % 2, y % 2) {
+                    (0, 0) => maze.set_path(&c, 0_f64),
+                    (1, 0) if x + 1 < width => maze.set_path(&c, 0_f64),
+                    (0, 1) if y + 1 < height => maze.set_path(&c, 0_f64),
+                    (_, _) => maze.set_wall(&c),
+                }
+            }
+        }
+        let chambers = vec![Chamber {
+            x: 0,
+            y: 0,
+            width: width.div_ceil(2),
+            height: height.div_ceil(2),
+        }];
+        RecursiveDivision { maze, chambers }
+    }
+
+    /* Add a vertical wall on the right of the column of cells `x` */
+    fn add_vertical_wall(&mut self, chamber: Chamber, x: usize) {
+        let gap = self
+            .maze
+            .rng
+            .random_range(chamber.y..chamber.y + chamber.height);
+        for y in (chamber.y..chamber.y + chamber.height).filter(|&y| y != gap) {
+            self.maze.set_wall(&Coord {
+                x: 2 * x + 1,
+                y: 2 * y,
+            });
+        }
+        self.chambers.push(Chamber {
+            width: x + 1 - chamber.x,
+            ..chamber
+        });
+        self.chambers.push(Chamber {
+            x: x + 1,
+            width: chamber.x + chamber.width - x - 1,
+            ..chamber
+        });
+    }
+
+    /* Add a horizontal wall below the row of cells `y` */
+    fn add_horizontal_wall(&mut self, chamber: Chamber, y: usize) {
+        let gap = self
+            .maze
+            .rng
+            .random_range(chamber.x..chamber.x + chamber.width);
+        for x in (chamber.x..chamber.x + chamber.width).filter(|&x| x != gap) {
+            self.maze.set_wall(&Coord {
+                x: 2 * x,
+                y: 2 * y + 1,
+            });
+        }
+        self.chambers.push(Chamber {
+            height: y + 1 - chamber.y,
+            ..chamber
+        });
+        self.chambers.push(Chamber {
+            y: y + 1,
+            height: chamber.y + chamber.height - y - 1,
+            ..chamber
+        });
+    }
+}
+
+impl<'a> Algorithm<'a> for RecursiveDivision<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        while let Some(chamber) = self.chambers.pop() {
+            let is_vertical = match (chamber.width > 1, chamber.height > 1) {
+                (false, false) => continue,
+                (true, false) => true,
+                (false, true) => false,
+                (true, true) => self.maze.rng.random::<f64>() < self.maze.vertical_bias,
+            };
+            if is_vertical {
+                let x = self
+                    .maze
+                    .rng
+                    .random_range(chamber.x..chamber.x + chamber.width - 1);
+                self.add_vertical_wall(chamber, x);
+            } else {
+                let y = self
+                    .maze
+                    .rng
+                    .random_range(chamber.y..chamber.y + chamber.height - 1);
+                self.add_horizontal_wall(chamber, y);
+            }
+            return Some(self.maze);
+        }
+        self.maze.finish();
+        None
+    }
+}
+
+/* }}} */
+/* Binary Tree {{{ */
+/*
+ * For each cell, remove either the wall above it or the wall on its left.
+ * Cells on the top row can only go left and cells on the left column can
+ * only go up.
+ *
+ * It needs no state at all but produces a strong diagonal texture, with
+ * long corridors along the top row and the left column. The diagonal bias
+ * is the probability to go up rather than left.
+ */
+struct BinaryTree<'a> {
+    maze: &'a mut Maze,
+    diagonal_bias: f64,
+    y: usize,
+}
+
+impl<'a> BinaryTree<'a> {
+    fn init(maze: &'a mut Maze, diagonal_bias: f64) -> BinaryTree<'a> {
+        BinaryTree {
+            maze,
+            diagonal_bias,
+            y: 0,
+        }
+    }
+}
+
+impl<'a> Algorithm<'a> for BinaryTree<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        if self.y >= self.maze.geometry.height {
+            self.maze.finish();
+            return None;
+        }
+        let y = self.y;
+        for x in (0..self.maze.geometry.width).step_by(2) {
+            self.maze.set_path(&Coord { x, y }, 0_f64);
+            let w = match (x, y) {
+                (0, 0) => continue,
+                (_, 0) => Coord { x: x - 1, y },
+                (0, _) => Coord { x, y: y - 1 },
+                (_, _) => {
+                    if self.maze.rng.random::<f64>() < self.diagonal_bias {
+                        Coord { x, y: y - 1 }
+                    } else {
+                        Coord { x: x - 1, y }
+                    }
+                }
+            };
+            self.maze.set_path(&w, 0_f64);
+        }
+        self.y += 2;
+        Some(self.maze)
+    }
+}
+
+/* }}} */
+/* Sidewinder {{{ */
+/*
+ * Row by row:
+ * 1. On the first row, remove all the walls: it is a single corridor.
+ * 2. On the other rows, walk from left to right and add the cells to the
+ *    current run. At each cell, randomly decide to close the run: remove
+ *    the wall above a random cell of the run and start a new run.
+ *    Otherwise, remove the wall on the right of the cell. The run is always
+ *    closed on the last cell of the row.
+ *
+ * The probability to close a run sets the length of the horizontal
+ * passages.
+ */
+struct Sidewinder<'a> {
+    maze: &'a mut Maze,
+    close_probability: f64,
+    y: usize,
+}
+
+impl<'a> Sidewinder<'a> {
+    fn init(maze: &'a mut Maze, close_probability: f64) -> Sidewinder<'a> {
+        Sidewinder {
+            maze,
+            close_probability,
+            y: 0,
+        }
+    }
+}
+
+impl<'a> Algorithm<'a> for Sidewinder<'a> {
+    fn next(&mut self) -> Option<&mut Maze> {
+        if self.y >= self.maze.geometry.height {
+            self.maze.finish();
+            return None;
+        }
+        let y = self.y;
+        let width = self.maze.geometry.width;
+        let mut run_start = 0;
+        for x in (0..width).step_by(2) {
+            self.maze.set_path(&Coord { x, y }, 0_f64);
+            let is_last = x + 2 >= width;
+            if y == 0 {
+                if !is_last {
+                    self.maze.set_path(&Coord { x: x + 1, y }, 0_f64);
+                }
+            } else if is_last || self.maze.rng.random::<f64>() < self.close_probability {
+                let cell = self.maze.rng.random_range(run_start / 2..=x / 2);
+                self.maze.set_path(
+                    &Coord {
+                        x: 2 * cell,
+                        y: y - 1,
+                    },
+                    0_f64,
+                );
+                run_start = x + 2;
+            } else {
+                self.maze.set_path(&Coord { x: x + 1, y }, 0_f64);
+            }
+        }
+        self.y += 2;
+        Some(self.maze)
+    }
+}
+
+/* }}} */
+
+#[derive(Debug, Clone, Copy)]
+pub enum AlgorithmKind {
+    Prim,
+    Kruskal,
+    Backtracker,
+    HuntAndKill,
+    Wilson,
+    /// Aldous-Broder random walk. With a `wilson_ratio`, switch to Wilson's
+    /// algorithm once that fraction of the cells has been visited.
+    AldousBroder {
+        wilson_ratio: Option<f64>,
+    },
+    Eller,
+    GrowingTree(GrowingTreePolicy),
+    RecursiveDivision,
+    /// Binary Tree, going up rather than left with a probability of
+    /// `diagonal_bias`.
+    BinaryTree {
+        diagonal_bias: f64,
+    },
+    /// Sidewinder, closing the horizontal runs with a probability of
+    /// `close_probability`.
+    Sidewinder {
+        close_probability: f64,
+    },
+}
+
+/* Maze {{{ */
+#[derive(Debug, Clone)]
+pub struct Maze {
+    geometry: super::Geometry,
+    grid: Vec<CellKind>,
+    vertical_bias: f64,
+    origin: Coord,
+    end: Coord,
+    len: f64,
+    iterations: u32,
+    seed: u64,
+    /* Row of the first line of the grid, for strips of a larger maze */
+    row_offset: usize,
+    rng: StdRng,
+    /* Region of the grid changed since animations last drew it */
+    changed: Option<(Coord, Coord)>,
+}
+
+impl Maze {
+    pub(crate) fn new(
+        g: &super::Geometry,
+        vertical_bias: f64,
+        origin: &super::Origin,
+        seed: u64,
+    ) -> Maze {
+        let mut m = Maze {
+            geometry: g.clone(),
+            grid: Vec::new(),
+            vertical_bias,
+            origin: Coord { x: 0, y: 0 },
+            len: 0_f64,
+            iterations: 0,
+            seed,
+            row_offset: 0,
+            rng: StdRng::seed_from_u64(seed),
+            changed: None,
+            end: Coord {
+                x: g.width - 1,
+                y: g.height - 1,
+            },
+        };
+        m.grid.reserve(g.width * g.height);
+        for _ in 0..(g.width * g.height) {
+            m.grid.push(CellKind::Undefined);
+        }
+        m.origin = m.origin_to_coord(origin);
+        m
+    }
+
+    pub fn origin(&self) -> Coord {
+        self.origin.clone()
+    }
+    pub fn end(&self) -> Coord {
+        self.end.clone()
+    }
+    pub fn len(&self) -> f64 {
+        self.len
+    }
+    pub fn geometry(&self) -> super::Geometry {
+        self.geometry.clone()
+    }
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+    /// Seed used to generate the maze.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Random number generator dedicated to the cell `c`, derived from the
+    /// seed of the maze. Renderers use it so that drawing a maze twice
+    /// gives the same image.
+    pub fn cell_rng(&self, c: &Coord) -> StdRng {
+        let x = (c.x as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+        let y = ((self.row_offset + c.y) as u64).wrapping_mul(0xc2b2_ae3d_27d4_eb4f);
+        StdRng::seed_from_u64(self.seed ^ x ^ y.rotate_left(32))
+    }
+
+    fn origin_to_coord(&self, origin: &super::Origin) -> Coord {
+        let x = origin.x * (self.geometry.width as f64);
+        let y = origin.y * (self.geometry.height as f64);
+        Coord {
+            x: (x as usize) & !1,
+            y: (y as usize) & !1,
+        }
+    }
+
+    pub fn cell_kind(&self, c: &Coord) -> CellKind {
+        if c.x >= self.geometry.width || c.y >= self.geometry.height {
+            CellKind::Undefined
+        } else {
+            self.grid[c.y * self.geometry.width + c.x].clone()
+        }
+    }
+
+    /* Every change to the grid goes through here, to keep track of the
+     * region changed by each step of the algorithms */
+    fn set_cell(&mut self, c: &Coord, cell_kind: CellKind) {
+        self.grid[c.y * self.geometry.width + c.x] = cell_kind;
+        self.changed = Some(match self.changed.take() {
+            Some((min, max)) => (
+                Coord {
+                    x: min.x.min(c.x),
+                    y: min.y.min(c.y),
+                },
+                Coord {
+                    x: max.x.max(c.x),
+                    y: max.y.max(c.y),
+                },
+            ),
+            None => (c.clone(), c.clone()),
+        });
+    }
+
+    /* Smallest rectangle of the grid, as its top left and bottom right
+     * cells, holding the cells changed since the previous call */
+    fn take_changes(&mut self) -> Option<(Coord, Coord)> {
+        self.changed.take()
+    }
+
+    fn set_path(&mut self, c: &Coord, d: f64) {
+        self.set_cell(c, CellKind::PathKind(d));
+    }
+
+    fn set_wall(&mut self, c: &Coord) {
+        if let CellKind::WallKind = self.cell_kind(c) {
+            return;
+        }
+        self.set_cell(c, CellKind::WallKind);
+    }
+
+    fn get_coord_up(&self, c: &Coord) -> Option<Coord> {
+        if c.y == 0 {
+            None
+        } else {
+            Some(Coord { x: c.x, y: c.y - 1 })
+        }
+    }
+    fn get_coord_down(&self, c: &Coord) -> Option<Coord> {
+        if c.y >= self.geometry.height - 1 {
+            None
+        } else {
+            Some(Coord { x: c.x, y: c.y + 1 })
+        }
+    }
+    fn get_coord_left(&self, c: &Coord) -> Option<Coord> {
+        if c.x == 0 {
+            None
+        } else {
+            Some(Coord { x: c.x - 1, y: c.y })
+        }
+    }
+    fn get_coord_right(&self, c: &Coord) -> Option<Coord> {
+        if c.x >= self.geometry.width - 1 {
+            None
+        } else {
+            Some(Coord { x: c.x + 1, y: c.y })
+        }
+    }
+
+    fn get_coord_next(&self, c: &Coord, dir: &Direction) -> Option<Coord> {
+        match *dir {
+            Direction::Up => self.get_coord_up(c),
+            Direction::Down => self.get_coord_down(c),
+            Direction::Left => self.get_coord_left(c),
+            Direction::Right => self.get_coord_right(c),
+        }
+    }
+
+    /* Neighbour of `c` in the direction `dir`, if it is a path */
+    pub(crate) fn path_next(&self, c: &Coord, dir: &Direction) -> Option<Coord> {
+        self.get_coord_next(c, dir)
+            .filter(|n| matches!(self.cell_kind(n), CellKind::PathKind(_)))
+    }
+
+    fn get_coord_twice(&self, c: &Coord, dir: &Direction) -> Option<Coord> {
+        match *dir {
+            Direction::Up => self.get_coord_up(c).and_then(|n| self.get_coord_up(&n)),
+            Direction::Down => self.get_coord_down(c).and_then(|n| self.get_coord_down(&n)),
+            Direction::Left => self.get_coord_left(c).and_then(|n| self.get_coord_left(&n)),
+            Direction::Right => self
+                .get_coord_right(c)
+                .and_then(|n| self.get_coord_right(&n)),
+        }
+    }
+
+    /* Cells sitting at distance 2 of `c`, that is cells that could be
+     * connected to `c` by removing the wall between them */
+    fn get_cells_around(&self, c: &Coord) -> Vec<Coord> {
+        let dirs = vec![
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        let mut v: Vec<Coord> = Vec::with_capacity(4);
+        for d in dirs {
+            if let Some(n) = self.get_coord_twice(c, &d) {
+                v.push(n);
+            }
+        }
+        v
+    }
+
+    /* All the cells of the grid. Walls sit between them, at odd
+     * coordinates */
+    fn cells(&self) -> Vec<Coord> {
+        let mut v: Vec<Coord> = Vec::with_capacity(self.geometry.width * self.geometry.height / 4);
+        for y in (0..self.geometry.height).step_by(2) {
+            for x in (0..self.geometry.width).step_by(2) {
+                v.push(Coord { x, y });
+            }
+        }
+        v
+    }
+
+    fn get_random_wall_direction(&mut self, w: &Wall) -> Option<Direction> {
+        match (w.x % 2, w.y % 2) {
+            (0, 1) => match self.rng.random::<u8>() % 2 {
+                0 => Some(Direction::Up),
+                _ => Some(Direction::Down),
+            },
+            (1, 0) => match self.rng.random::<u8>() % 2 {
+                0 => Some(Direction::Left),
+                _ => Some(Direction::Right),
+            },
+            (1, 1) => {
+                let f = self.rng.random::<f64>();
+                if f < self.vertical_bias {
+                    match self.rng.random::<u8>() % 2 {
+                        0 => Some(Direction::Left),
+                        _ => Some(Direction::Right),
+                    }
+                } else {
+                    match self.rng.random::<u8>() % 2 {
+                        0 => Some(Direction::Up),
+                        _ => Some(Direction::Down),
+                    }
+                }
+            }
+            (_, _) => {
+                /* (0, 0) */
+                None
+            }
+        }
+    }
+
+    /* Draw the maze at its place in an image filled with the background
+     * color */
+    pub(crate) fn draw_layout<T: ?Sized + Rendering>(
+        &self,
+        renderer: &T,
+        layout: &super::Layout,
+    ) -> RgbImage {
+        let mut img = RgbImage::from_pixel(
+            layout.geometry.width as u32,
+            layout.geometry.height as u32,
+            renderer.cell_color(&CellKind::WallKind),
+        );
+        image::imageops::replace(
+            &mut img,
+            &self.draw(renderer),
+            layout.left as i64,
+            layout.top as i64,
+        );
+        img
+    }
+
+    /* Until the maze is complete, paths hold distances rather than values
+     * in [0, 1]: they are drawn scaled by the largest one, so that the
+     * gradient of an animation stretches as the maze grows */
+    fn path_scale(&self) -> f64 {
+        let max = self
+            .grid
+            .iter()
+            .filter_map(|k| match *k {
+                CellKind::PathKind(f) if f.is_finite() => Some(f),
+                _ => None,
+            })
+            .fold(1_f64, f64::max);
+        /* Rounded up to a power of 2^(1/8), so that the scale, and with it
+         * the color of every path, only changes once in a while */
+        ((max.log2() * 8_f64).ceil() / 8_f64).exp2()
+    }
+
+    fn drawn_cell_kind(&self, c: &Coord, scale: f64) -> CellKind {
+        match self.cell_kind(c) {
+            CellKind::PathKind(f) if f.is_nan() => CellKind::PathKind(0_f64),
+            CellKind::PathKind(f) => CellKind::PathKind((f / scale).clamp(0_f64, 1_f64)),
+            k => k,
+        }
+    }
+
+    pub fn draw<T: ?Sized + Rendering>(&self, renderer: &T) -> RgbImage {
+        let g = image_geometry(renderer, &self.geometry);
+        let mut img = RgbImage::new(g.width as u32, g.height as u32);
+
+        let scale = self.path_scale();
+        for y in 0..self.geometry.height {
+            for x in 0..self.geometry.width {
+                let c = Coord { x, y };
+                renderer.draw_cell(self, &mut img, &c, self.drawn_cell_kind(&c, scale));
+            }
+        }
+        img
+    }
+    /* Breadth-first walk of the paths from the `starts` cells, returning the
+     * distance of every reachable cell, indexed like the grid */
+    pub(crate) fn distances(&self, starts: &[Coord]) -> Vec<Option<u32>> {
+        self.distances_through(starts, |_| true)
+    }
+
+    /* Same, only going through the paths for which `is_open` is true */
+    fn distances_through<F: Fn(&Coord) -> bool>(
+        &self,
+        starts: &[Coord],
+        is_open: F,
+    ) -> Vec<Option<u32>> {
+        let mut distances: Vec<Option<u32>> = vec![None; self.grid.len()];
+        let mut queue: VecDeque<Coord> = VecDeque::new();
+        for c in starts {
+            if let CellKind::PathKind(_) = self.cell_kind(c) {
+                distances[c.y * self.geometry.width + c.x] = Some(0);
+                queue.push_back(c.clone());
+            }
+        }
+        let dirs = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        while let Some(c) = queue.pop_front() {
+            let d = distances[c.y * self.geometry.width + c.x].unwrap();
+            for dir in &dirs {
+                if let Some(n) = self.get_coord_next(&c, dir) {
+                    let idx = n.y * self.geometry.width + n.x;
+                    if distances[idx].is_none() && is_open(&n) {
+                        if let CellKind::PathKind(_) = self.grid[idx] {
+                            distances[idx] = Some(d + 1);
+                            queue.push_back(n);
+                        }
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    /* Last step of the algorithms carving paths in any order: mark unvisited
+     * as walls, find the end as the furthest cell from the origin and set
+     * path values to the normalized distance from the origin */
+    fn finish(&mut self) {
+        for cell in self.grid.iter_mut() {
+            if let CellKind::Undefined = cell {
+                *cell = CellKind::WallKind;
+            }
+        }
+        let distances = self.distances(std::slice::from_ref(&self.origin));
+        self.len = 0_f64;
+        self.end = self.origin.clone();
+        for (i, d) in distances.iter().enumerate() {
+            if let Some(d) = d {
+                if f64::from(*d) > self.len {
+                    self.len = f64::from(*d);
+                    self.end = Coord {
+                        x: i % self.geometry.width,
+                        y: i / self.geometry.width,
+                    };
+                }
+            }
+        }
+        for (cell, d) in self.grid.iter_mut().zip(distances) {
+            if let (CellKind::PathKind(_), Some(d)) = (&cell, d) {
+                if self.len > 0_f64 {
+                    *cell = CellKind::PathKind(f64::from(d) / self.len);
+                } else {
+                    *cell = CellKind::PathKind(0_f64);
+                }
+            }
+        }
+    }
+
+    fn walk<F>(&self, start: &Coord, is_visited: F) -> Option<(Coord, Direction)>
     where
         F: Fn(&Maze, &Coord) -> Option<bool>,
     {
@@ -814,76 +1930,175 @@ impl Maze {
         None
     }
 
-    fn compute_solution(&mut self) {
-        self.clear_path();
-        let mut sol: Vec<(Coord, Direction)> = Vec::new();
-        let mut c = self.origin.clone();
-        let is_visited = |m: &Maze, c: &Coord| m.is_visited(c);
-        self.set_path(&c, 0_f64);
-        loop {
-            match self.walk(&c, is_visited) {
-                Some((next, direction)) => {
-                    self.set_path(&next, 0_f64);
-                    if (next.x, next.y) == (self.end.x, self.end.y) {
-                        break;
+    /* Cells visited by a breadth-first search from the origin until it
+     * reaches the end, grouped by distance to the origin */
+    pub(crate) fn solve_steps(&self) -> Vec<Vec<Coord>> {
+        let distances = self.distances(std::slice::from_ref(&self.origin));
+        let end = match distances[self.end.y * self.geometry.width + self.end.x] {
+            Some(d) => d as usize,
+            None => return Vec::new(),
+        };
+        let mut steps: Vec<Vec<Coord>> = vec![Vec::new(); end + 1];
+        for y in 0..self.geometry.height {
+            for x in 0..self.geometry.width {
+                if let Some(d) = distances[y * self.geometry.width + x] {
+                    if (d as usize) <= end {
+                        steps[d as usize].push(Coord { x, y });
                     }
-                    sol.push((c, direction));
-                    c = next;
                 }
-                None => {
-                    if let Some((next, _)) = sol.pop() {
-                        c = next;
+            }
+        }
+        steps
+    }
+
+    /// Shortest path from the origin to the end of the maze, both included.
+    /// Empty if the end can not be reached.
+    pub fn solution(&self) -> Vec<Coord> {
+        self.solution_through(|_| true)
+    }
+
+    /* Shortest path from the origin to the end, only going through the paths
+     * for which `is_open` is true */
+    pub(crate) fn solution_through<F: Fn(&Coord) -> bool>(&self, is_open: F) -> Vec<Coord> {
+        let distances = self.distances_through(std::slice::from_ref(&self.origin), is_open);
+        let dirs = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        let mut sol: Vec<Coord> = Vec::new();
+        let mut c = self.end.clone();
+        /* Walk back from the end, following decreasing distances to the
+         * origin, which works even when there are several routes */
+        let mut d = match distances[c.y * self.geometry.width + c.x] {
+            Some(d) => d,
+            None => return sol,
+        };
+        sol.push(c.clone());
+        while d > 0 {
+            for dir in &dirs {
+                if let Some(n) = self.get_coord_next(&c, dir) {
+                    if distances[n.y * self.geometry.width + n.x] == Some(d - 1) {
+                        c = n;
+                        break;
                     }
                 }
             }
+            d -= 1;
+            sol.push(c.clone());
         }
-        /* compute lengths */
-        self.clear_path();
-        /* mark solution as 0 */
-        for v in &sol {
-            let c = &v.0;
-            self.grid[c.y * self.geometry.width + c.x] = CellKind::PathKind(0_f64);
-        }
-        let mut len = 0_f64;
-        for v in &sol {
-            let mut stack: Vec<Coord> = Vec::new();
-            let mut d = 0_f64;
-            c = v.0.clone();
-            loop {
-                match self.walk(&c, is_visited) {
-                    Some((next, _)) => {
-                        d += 1_f64;
-                        if d > len {
-                            len = d;
-                        }
-                        self.set_path(&next, d);
-                        stack.push(c);
-                        c = next;
+        sol.reverse();
+        sol
+    }
+
+    /* Set path values to the shortest distance from the relative point
+     * `from`, whatever the algorithm left in them */
+    pub(crate) fn compute_distances(&mut self, from: &super::Origin) {
+        let from = self.origin_to_coord(from);
+        let distances = self.distances(std::slice::from_ref(&from));
+        let len = distances.iter().flatten().max().copied().unwrap_or(0);
+        for (cell, d) in self.grid.iter_mut().zip(distances) {
+            if let CellKind::PathKind(_) = cell {
+                *cell = match d {
+                    Some(d) if len > 0 => CellKind::PathKind(f64::from(d) / f64::from(len)),
+                    Some(_) => CellKind::PathKind(0_f64),
+                    None => CellKind::PathKind(1_f64),
+                };
+            }
+        }
+    }
+
+    pub(crate) fn compute_solution(&mut self) {
+        let sol = self.solution();
+        /* compute lengths: the solution is at 0 */
+        let distances = self.distances(&sol);
+        let len = distances.iter().flatten().max().copied().unwrap_or(0);
+        let len = f64::from(len).log10();
+        for (cell, d) in self.grid.iter_mut().zip(distances) {
+            if let CellKind::PathKind(_) = cell {
+                *cell = match d {
+                    Some(d) if d > 0 && len > 0_f64 => {
+                        CellKind::PathKind(f64::from(d).log10() / len)
                     }
-                    None => match stack.pop() {
-                        Some(next) => {
-                            c = next.clone();
-                            if let CellKind::PathKind(f) = self.cell_kind(&c) {
-                                d = f;
-                            }
-                        }
-                        None => {
-                            break;
-                        }
-                    },
-                }
+                    Some(_) => CellKind::PathKind(0_f64),
+                    None => CellKind::PathKind(1_f64),
+                };
             }
         }
-        len = len.log10();
-        for y in 0..self.geometry.height {
-            for x in 0..self.geometry.width {
-                if let CellKind::PathKind(f) = self.cell_kind(&Coord { x, y }) {
-                    if f > 0_f64 {
-                        self.grid[y * self.geometry.width + x] =
-                            CellKind::PathKind(f.log10() / len);
+    }
+
+    /// Remove a fraction `ratio` of the dead ends by knocking out one of
+    /// their walls, preferably towards another dead end. This adds loops to
+    /// the maze. Path values, end and length are then computed again as the
+    /// shortest distances from the origin.
+    pub fn braid(&mut self, ratio: f64) {
+        let dirs = [
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ];
+        let mut dead_ends: Vec<Coord> = self
+            .cells()
+            .into_iter()
+            .filter(|c| self.is_dead_end(c))
+            .collect();
+        dead_ends.shuffle(&mut self.rng);
+        /* knocking out a wall between two dead ends removes both of them */
+        let mut to_remove = (ratio * dead_ends.len() as f64).round() as usize;
+        for c in dead_ends {
+            if to_remove == 0 {
+                break;
+            }
+            /* a previous wall removal may have already fixed it */
+            if !self.is_dead_end(&c) {
+                continue;
+            }
+            let mut walls: Vec<(Coord, bool)> = Vec::with_capacity(3);
+            for dir in &dirs {
+                if let (Some(w), Some(n)) =
+                    (self.get_coord_next(&c, dir), self.get_coord_twice(&c, dir))
+                {
+                    if let (CellKind::WallKind, CellKind::PathKind(_)) =
+                        (self.cell_kind(&w), self.cell_kind(&n))
+                    {
+                        walls.push((w, self.is_dead_end(&n)));
                     }
                 }
             }
+            if to_remove == 1 {
+                /* do not remove one dead end too many */
+                walls.retain(|(_, is_dead_end)| !*is_dead_end);
+            } else if walls.iter().any(|(_, is_dead_end)| *is_dead_end) {
+                walls.retain(|(_, is_dead_end)| *is_dead_end);
+            }
+            if walls.is_empty() {
+                continue;
+            }
+            let r: usize = self.rng.random_range(0..walls.len());
+            let (w, is_dead_end) = walls.swap_remove(r);
+            self.set_path(&w, 0_f64);
+            to_remove -= if is_dead_end { 2 } else { 1 };
+        }
+        self.finish();
+    }
+
+    fn is_dead_end(&self, c: &Coord) -> bool {
+        if let CellKind::PathKind(_) = self.cell_kind(c) {
+            let dirs = [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ];
+            dirs.iter()
+                .filter_map(|d| self.get_coord_next(c, d))
+                .filter(|n| matches!(self.cell_kind(n), CellKind::PathKind(_)))
+                .count()
+                == 1
+        } else {
+            false
         }
     }
 }
@@ -898,11 +2113,39 @@ fn generate_algorithm<'a>(
         AlgorithmKind::Prim => Box::new(Prim::init(maze)),
         AlgorithmKind::Kruskal => Box::new(Kruskal::init(maze)),
         AlgorithmKind::Backtracker => Box::new(Backtracker::init(maze)),
+        AlgorithmKind::HuntAndKill => Box::new(HuntAndKill::init(maze)),
+        AlgorithmKind::Wilson => Box::new(Wilson::init(maze)),
+        AlgorithmKind::AldousBroder { wilson_ratio: None } => Box::new(AldousBroder::init(maze)),
+        AlgorithmKind::AldousBroder {
+            wilson_ratio: Some(ratio),
+        } => Box::new(AldousBroderWilson::init(maze, ratio)),
+        AlgorithmKind::Eller => Box::new(Eller::init(maze)),
+        AlgorithmKind::GrowingTree(policy) => Box::new(GrowingTree::init(maze, policy)),
+        AlgorithmKind::RecursiveDivision => Box::new(RecursiveDivision::init(maze)),
+        AlgorithmKind::BinaryTree { diagonal_bias } => {
+            Box::new(BinaryTree::init(maze, diagonal_bias))
+        }
+        AlgorithmKind::Sidewinder { close_probability } => {
+            Box::new(Sidewinder::init(maze, close_probability))
+        }
     }
 }
 
 pub trait Rendering {
     fn tile_size(&self) -> usize;
+    /// Position in pixels of the column or row `i` of the grid. Tiles are
+    /// squares of `tile_size()` unless overridden.
+    fn tile_offset(&self, i: usize) -> usize {
+        i * self.tile_size()
+    }
+    /// Size in pixels of an image showing `n` columns or rows of the grid.
+    fn image_length(&self, n: usize) -> usize {
+        self.tile_offset(n)
+    }
+    /// Number of columns or rows of the grid fitting in `pixels`.
+    fn grid_length(&self, pixels: usize) -> usize {
+        pixels / self.tile_size()
+    }
     fn draw_cell(&self, maze: &Maze, img: &mut RgbImage, c: &Coord, cell_kind: CellKind);
     fn draw_cell_gif(
         &self,
@@ -912,103 +2155,675 @@ pub trait Rendering {
         c: &Coord,
         cell_kind: CellKind,
     );
+    /// Colors of gif images, as RGB triplets. Animations need one more
+    /// color for transparency, so there should be at most 255 of them.
     fn get_gif_palette(&self) -> Vec<u8>;
+    /// Flat color of a cell, for outputs that do not draw tiles.
+    fn cell_color(&self, cell_kind: &CellKind) -> Rgb<u8>;
 }
 
-fn grid_geometry<T: ?Sized + Rendering>(renderer: &T, g: &super::Geometry) -> super::Geometry {
-    let tile_size = renderer.tile_size();
+pub(crate) fn grid_geometry<T: ?Sized + Rendering>(
+    renderer: &T,
+    g: &super::Geometry,
+) -> super::Geometry {
     super::Geometry {
-        width: g.width / tile_size,
-        height: g.height / tile_size,
+        width: renderer.grid_length(g.width),
+        height: renderer.grid_length(g.height),
     }
 }
 
-fn image_geometry<T: ?Sized + Rendering>(renderer: &T, g: &super::Geometry) -> super::Geometry {
-    let tile_size = renderer.tile_size();
+pub(crate) fn image_geometry<T: ?Sized + Rendering>(
+    renderer: &T,
+    g: &super::Geometry,
+) -> super::Geometry {
     super::Geometry {
-        width: g.width * tile_size,
-        height: g.height * tile_size,
+        width: renderer.image_length(g.width),
+        height: renderer.image_length(g.height),
+    }
+}
+
+pub(crate) fn generate<F>(maze: &mut Maze, algorithm: AlgorithmKind, mut on_step: F)
+where
+    F: FnMut(&mut Maze),
+{
+    let mut nb_iterations = 0u32;
+    {
+        let mut a = generate_algorithm(maze, algorithm);
+        while let Some(m) = a.next() {
+            nb_iterations += 1u32;
+            on_step(m);
+        }
+    }
+    maze.iterations = nb_iterations;
+}
+
+/// How the generation of a maze is turned into an animation.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct AnimationSettings {
+    /* Number of steps of the algorithm between two frames */
+    pub(crate) frame_every: u32,
+    /* Maximum number of frames showing the generation, raising frame_every
+     * if needed */
+    pub(crate) max_frames: Option<u32>,
+    pub(crate) fps: f64,
+    /* Time the complete maze is shown, in seconds */
+    pub(crate) final_hold: f64,
+    pub(crate) loop_forever: bool,
+    /* Colors of the cells visited while solving the maze and of the
+     * solution, to show it being solved once complete */
+    pub(crate) solve: Option<[Rgb<u8>; 2]>,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> Self {
+        AnimationSettings {
+            frame_every: 1,
+            max_frames: None,
+            fps: 50_f64,
+            final_hold: 1_f64,
+            loop_forever: true,
+            solve: None,
+        }
+    }
+}
+
+impl AnimationSettings {
+    /* Number of steps of the algorithm between two frames. `steps` gives
+     * the number of steps of the generation, only computed when the number
+     * of frames is limited. */
+    fn interval<F: FnOnce() -> u32>(&self, steps: F) -> u32 {
+        let every = self.frame_every.max(1);
+        match self.max_frames {
+            Some(max) => every.max(steps().div_ceil(max.max(1))),
+            None => every,
+        }
+    }
+
+    /* Same settings, drawing a frame every `interval` steps whatever their
+     * number */
+    fn sampled(&self, interval: u32) -> AnimationSettings {
+        AnimationSettings {
+            frame_every: interval,
+            max_frames: None,
+            ..*self
+        }
+    }
+
+    fn frame_delay(&self) -> Duration {
+        Duration::from_secs_f64(1_f64 / self.fps)
+    }
+
+    fn final_delay(&self) -> Duration {
+        Duration::from_secs_f64(self.final_hold)
+    }
+}
+
+/* Delays of gif frames are in hundredths of second. Players take a delay
+ * of 0 as a default one, so frames last at least a hundredth of second. */
+fn centiseconds(delay: Duration) -> u16 {
+    (delay.as_secs_f64() * 100_f64)
+        .round()
+        .clamp(1_f64, f64::from(u16::MAX)) as u16
+}
+
+/* Delays of animated PNG frames are fractions of second, as numerator and
+ * denominator: use milliseconds, or coarser units for delays too long to
+ * count in milliseconds */
+fn apng_delay(delay: Duration) -> (u16, u16) {
+    for den in [1000_u16, 100, 10] {
+        let num = (delay.as_secs_f64() * f64::from(den)).round();
+        if num <= f64::from(u16::MAX) {
+            return (num as u16, den);
+        }
+    }
+    (
+        delay.as_secs_f64().round().min(f64::from(u16::MAX)) as u16,
+        1,
+    )
+}
+
+/* Number of steps the algorithm takes to generate the maze, found by running
+ * it on a copy, from the same random state */
+fn count_steps(maze: &Maze, algorithm: AlgorithmKind) -> u32 {
+    let mut counter = maze.clone();
+    generate(&mut counter, algorithm, |_| {});
+    counter.iterations
+}
+
+/* Tiles of `cells` as a rectangle in pixels: left, right, top and bottom */
+fn tiles_rect<T: ?Sized + Rendering>(
+    renderer: &T,
+    cells: &[Coord],
+) -> (usize, usize, usize, usize) {
+    let (mut min, mut max) = match cells.first() {
+        Some(c) => (c.clone(), c.clone()),
+        None => (Coord { x: 0, y: 0 }, Coord { x: 0, y: 0 }),
+    };
+    for c in cells {
+        min = Coord {
+            x: min.x.min(c.x),
+            y: min.y.min(c.y),
+        };
+        max = Coord {
+            x: max.x.max(c.x),
+            y: max.y.max(c.y),
+        };
     }
+    (
+        renderer.tile_offset(min.x),
+        renderer.tile_offset(max.x + 1),
+        renderer.tile_offset(min.y),
+        renderer.tile_offset(max.y + 1),
+    )
 }
 
-#[allow(clippy::too_many_arguments)]
-pub fn generate_image<T: ?Sized + Rendering>(
-    path: &path::Path,
-    g: super::Geometry,
+/* Paint the tiles of `cells` with a flat color, over an image of the maze
+ * placed with `layout` */
+pub(crate) fn paint_cells<T: ?Sized + Rendering>(
+    img: &mut RgbImage,
     renderer: &T,
-    vertical_bias: f64,
-    origin: super::Origin,
-    gradient: Option<Gradient>,
+    layout: &super::Layout,
+    cells: &[Coord],
+    color: Rgb<u8>,
+) {
+    for c in cells {
+        for y in renderer.tile_offset(c.y)..renderer.tile_offset(c.y + 1) {
+            for x in renderer.tile_offset(c.x)..renderer.tile_offset(c.x + 1) {
+                img.put_pixel((layout.left + x) as u32, (layout.top + y) as u32, color);
+            }
+        }
+    }
+}
+
+/* Generate the maze, calling `on_frame` every `every` steps of the
+ * algorithm */
+fn generate_sampled<F: FnMut(&mut Maze)>(
+    maze: &mut Maze,
     algorithm: AlgorithmKind,
-    animation: bool,
+    every: u32,
+    mut on_frame: F,
 ) {
-    let grid_geometry = grid_geometry(renderer, &g);
+    let mut step = 0u32;
+    generate(maze, algorithm, |m: &mut Maze| {
+        step += 1;
+        if step.is_multiple_of(every) {
+            on_frame(m);
+        }
+    });
+}
 
-    let mut maze = Maze::new(&grid_geometry, vertical_bias, &origin);
-    let mut nb_iterations = 0u32;
-    let g = image_geometry(renderer, &maze.geometry);
-    let width: u16 = g.width as u16;
-    let height: u16 = g.height as u16;
+fn gif_length(pixels: usize) -> u16 {
+    u16::try_from(pixels).expect("gif images can not be larger than 65535 pixels")
+}
 
-    let mut image = match animation {
-        true => Some(File::create(path).unwrap()),
-        false => None,
-    };
-    let mut encoder = match animation {
-        true => {
-            let palette = renderer.get_gif_palette();
-            Some(Encoder::new(image.as_mut().unwrap(), width, height, &palette).unwrap())
+/* Gif frames of an animation. Each frame only covers the cells changed
+ * since the previous one, the pixels that kept their color being
+ * transparent. */
+struct GifFrames<'r, T: ?Sized> {
+    renderer: &'r T,
+    layout: &'r super::Layout,
+    /* Size of the maze in pixels and its current image */
+    geometry: super::Geometry,
+    buffer: Vec<u8>,
+    /* Scale of the path values in the current image */
+    scale: f64,
+    transparent: Option<u8>,
+}
+
+impl<'r, T: ?Sized + Rendering> GifFrames<'r, T> {
+    fn new(
+        maze: &Maze,
+        renderer: &'r T,
+        layout: &'r super::Layout,
+        transparent: Option<u8>,
+    ) -> Self {
+        let geometry = image_geometry(renderer, &maze.geometry);
+        GifFrames {
+            renderer,
+            layout,
+            buffer: vec![0; geometry.width * geometry.height],
+            geometry,
+            scale: f64::NAN,
+            transparent,
         }
-        false => None,
-    };
+    }
 
-    let draw_animated_frame = |m: &Maze, enc: &mut Encoder<&mut File>| {
-        let f = m.draw_gif(renderer);
-        enc.write_frame(&f).unwrap();
-    };
+    /* Draw the changes made to the maze since the previous frame. The whole
+     * maze is drawn when the scale of the path values changes. */
+    fn next(&mut self, maze: &mut Maze) -> Frame<'static> {
+        let changes = maze.take_changes();
+        if maze.path_scale() != self.scale {
+            return self.full(maze);
+        }
+        /* Even without changes, a frame keeps the timing of the animation */
+        let (min, max) = changes.unwrap_or((Coord { x: 0, y: 0 }, Coord { x: 0, y: 0 }));
+        self.draw(maze, &min, &max, self.transparent)
+    }
 
-    {
-        let mut a = generate_algorithm(&mut maze, algorithm);
-        while let Some(m) = a.next() {
-            nb_iterations += 1u32;
-            if !animation {
-                continue;
+    fn full(&mut self, maze: &mut Maze) -> Frame<'static> {
+        maze.take_changes();
+        self.scale = maze.path_scale();
+        let max = Coord {
+            x: maze.geometry.width - 1,
+            y: maze.geometry.height - 1,
+        };
+        self.draw(maze, &Coord { x: 0, y: 0 }, &max, None)
+    }
+
+    /* Start and end in pixels of the tiles from `min` to `max`, counting the
+     * borders renderers may draw around the first and last tiles */
+    fn span(&self, min: usize, max: usize, n: usize) -> (usize, usize) {
+        let start = if min == 0 {
+            0
+        } else {
+            self.renderer.tile_offset(min)
+        };
+        let end = if max + 1 == n {
+            self.renderer.image_length(n)
+        } else {
+            self.renderer.tile_offset(max + 1)
+        };
+        (start, end)
+    }
+
+    fn draw(
+        &mut self,
+        maze: &Maze,
+        min: &Coord,
+        max: &Coord,
+        transparent: Option<u8>,
+    ) -> Frame<'static> {
+        let (left, right) = self.span(min.x, max.x, maze.geometry.width);
+        let (top, bottom) = self.span(min.y, max.y, maze.geometry.height);
+        let width = self.geometry.width;
+        let previous: Vec<u8> = (top..bottom)
+            .flat_map(|y| self.buffer[y * width + left..y * width + right].to_vec())
+            .collect();
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let c = Coord { x, y };
+                let cell_kind = maze.drawn_cell_kind(&c, self.scale);
+                self.renderer
+                    .draw_cell_gif(maze, &self.geometry, &mut self.buffer, &c, cell_kind);
+            }
+        }
+
+        let mut pixels: Vec<u8> = (top..bottom)
+            .flat_map(|y| self.buffer[y * width + left..y * width + right].to_vec())
+            .collect();
+        if let Some(t) = transparent {
+            for (p, old) in pixels.iter_mut().zip(previous) {
+                if *p == old {
+                    *p = t;
+                }
             }
-            dbg!("{} frames generated in gif", nb_iterations);
-            draw_animated_frame(m, encoder.as_mut().unwrap());
         }
+        Frame {
+            left: gif_length(self.layout.left + left),
+            top: gif_length(self.layout.top + top),
+            width: gif_length(right - left),
+            height: gif_length(bottom - top),
+            transparent,
+            dispose: DisposalMethod::Keep,
+            buffer: Cow::Owned(pixels),
+            ..Default::default()
+        }
+    }
+
+    /* Paint the tiles of `cells` with a flat color over the previous frames.
+     * The frame has its own palette, of that color and a transparent one. */
+    fn paint(&self, cells: &[Coord], color: Rgb<u8>) -> Frame<'static> {
+        let (left, right, top, bottom) = tiles_rect(self.renderer, cells);
+        let width = right - left;
+        let mut pixels = vec![1; width * (bottom - top)];
+        for c in cells {
+            for y in self.renderer.tile_offset(c.y)..self.renderer.tile_offset(c.y + 1) {
+                for x in self.renderer.tile_offset(c.x)..self.renderer.tile_offset(c.x + 1) {
+                    pixels[(y - top) * width + x - left] = 0;
+                }
+            }
+        }
+        Frame {
+            left: gif_length(self.layout.left + left),
+            top: gif_length(self.layout.top + top),
+            width: gif_length(width),
+            height: gif_length(bottom - top),
+            palette: Some(vec![color[0], color[1], color[2], 0, 0, 0]),
+            transparent: Some(1),
+            dispose: DisposalMethod::Keep,
+            buffer: Cow::Owned(pixels),
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) fn generate_animation<T: ?Sized + Rendering, W: Write, F: Fn(&mut Maze)>(
+    maze: &mut Maze,
+    renderer: &T,
+    algorithm: AlgorithmKind,
+    layout: &super::Layout,
+    settings: &AnimationSettings,
+    w: W,
+    post_process: F,
+) -> io::Result<()> {
+    let width = gif_length(layout.geometry.width);
+    let height = gif_length(layout.geometry.height);
+
+    /* Add a color to the palette as transparent color for the pixels that
+     * do not change between frames. Renderers leave room for it, but a full
+     * palette only gets full frames. */
+    let mut palette = renderer.get_gif_palette();
+    let transparent = if palette.len() < 3 * 256 {
+        let index = (palette.len() / 3) as u8;
+        palette.extend_from_slice(&[0, 0, 0]);
+        Some(index)
+    } else {
+        None
+    };
+    let mut encoder = Encoder::new(w, width, height, &palette).map_err(io::Error::other)?;
+    if settings.loop_forever {
+        encoder
+            .set_repeat(Repeat::Infinite)
+            .map_err(io::Error::other)?;
+    }
+    let mut write_frame = |f: &Frame| encoder.write_frame(f).map_err(io::Error::other);
+
+    /* Frames only cover the maze, fill the rest of the image once with the
+     * first color of the palette */
+    let g = image_geometry(renderer, &maze.geometry);
+    if g.width != layout.geometry.width || g.height != layout.geometry.height {
+        let background = Frame {
+            width,
+            height,
+            buffer: Cow::Owned(vec![0; layout.geometry.width * layout.geometry.height]),
+            delay: 1,
+            ..Default::default()
+        };
+        write_frame(&background)?;
+    }
+
+    let mut frames = GifFrames::new(maze, renderer, layout, transparent);
+    let settings = &settings.sampled(settings.interval(|| count_steps(maze, algorithm)));
+    /* keep the first error, the maze is still generated to the end */
+    let mut written = Ok(());
+    generate_sampled(maze, algorithm, settings.frame_every, |m: &mut Maze| {
+        let mut f = frames.next(m);
+        f.delay = centiseconds(settings.frame_delay());
+        if written.is_ok() {
+            written = write_frame(&f);
+        }
+    });
+    written?;
+    post_process(maze);
+
+    let mut f = frames.full(maze);
+    f.delay = centiseconds(settings.final_delay());
+    write_frame(&f)?;
+
+    if let Some([visited, solution]) = settings.solve {
+        for cells in maze.solve_steps() {
+            let mut f = frames.paint(&cells, visited);
+            f.delay = centiseconds(settings.frame_delay());
+            write_frame(&f)?;
+        }
+        let mut f = frames.paint(&maze.solution(), solution);
+        f.delay = centiseconds(settings.final_delay());
+        write_frame(&f)?;
     }
+    encoder.into_inner().map_err(io::Error::other)?;
+    Ok(())
+}
 
-    if animation {
-        if let Some(e) = encoder.as_mut() {
-            e.write_extension(ExtensionData::new_control_ext(
-                100u16,
-                DisposalMethod::Any,
-                false,
-                None,
-            ))
-            .unwrap();
-            draw_animated_frame(&maze, e);
-            e.write_extension(ExtensionData::Repetitions(Repeat::Infinite))
-                .unwrap();
+/* Generate the maze, calling `on_frame` with the full color images of the
+ * animation and their delay, until it fails */
+fn generate_frames<T, F, P>(
+    maze: &mut Maze,
+    renderer: &T,
+    algorithm: AlgorithmKind,
+    layout: &super::Layout,
+    settings: &AnimationSettings,
+    post_process: P,
+    mut on_frame: F,
+) -> io::Result<()>
+where
+    T: ?Sized + Rendering,
+    F: FnMut(&RgbImage, Duration) -> io::Result<()>,
+    P: Fn(&mut Maze),
+{
+    let mut written = Ok(());
+    generate_sampled(maze, algorithm, settings.frame_every, |m: &mut Maze| {
+        if written.is_ok() {
+            written = on_frame(&m.draw_layout(renderer, layout), settings.frame_delay());
         }
+    });
+    written?;
+    post_process(maze);
+    let mut img = maze.draw_layout(renderer, layout);
+    on_frame(&img, settings.final_delay())?;
+
+    if let Some([visited, solution]) = settings.solve {
+        for cells in maze.solve_steps() {
+            paint_cells(&mut img, renderer, layout, &cells, visited);
+            on_frame(&img, settings.frame_delay())?;
+        }
+        paint_cells(&mut img, renderer, layout, &maze.solution(), solution);
+        on_frame(&img, settings.final_delay())?;
+    }
+    Ok(())
+}
+
+pub(crate) fn generate_apng<T: ?Sized + Rendering, W: Write, F: Fn(&mut Maze)>(
+    maze: &mut Maze,
+    renderer: &T,
+    algorithm: AlgorithmKind,
+    layout: &super::Layout,
+    settings: &AnimationSettings,
+    w: W,
+    post_process: F,
+) -> io::Result<()> {
+    /* The number of frames comes before the first one: generate the maze a
+     * first time, from the same random state, to count them */
+    let mut counter = maze.clone();
+    generate(&mut counter, algorithm, |_| {});
+    let steps = counter.iterations;
+    let settings = &settings.sampled(settings.interval(|| steps));
+    let mut nb_frames = steps / settings.frame_every + 1;
+    if settings.solve.is_some() {
+        post_process(&mut counter);
+        nb_frames += counter.solve_steps().len() as u32 + 1;
     }
 
-    dbg!(
-        "On grid {:?}, from {:?} to {:?} (len: {}). Generated on {} iterations",
-        grid_geometry,
-        maze.origin(),
-        maze.end(),
-        maze.len().ceil(),
-        nb_iterations
+    let mut encoder = png::Encoder::new(
+        w,
+        layout.geometry.width as u32,
+        layout.geometry.height as u32,
     );
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    let nb_plays = if settings.loop_forever { 0 } else { 1 };
+    encoder.set_animated(nb_frames, nb_plays)?;
+    let mut writer = encoder.write_header()?;
+    generate_frames(
+        maze,
+        renderer,
+        algorithm,
+        layout,
+        settings,
+        post_process,
+        |img, delay| {
+            let (num, den) = apng_delay(delay);
+            writer.set_frame_delay(num, den)?;
+            writer.write_image_data(img.as_raw())?;
+            Ok(())
+        },
+    )?;
+    writer.finish()?;
+    Ok(())
+}
+
+fn write_riff_chunk(out: &mut Vec<u8>, name: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(name);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+/* The WebP encoder only writes still images: each frame is encoded as one,
+ * and its lossless bitstream moved into an animation frame of the extended
+ * file format. */
+pub(crate) fn generate_webp<T: ?Sized + Rendering, W: Write, F: Fn(&mut Maze)>(
+    maze: &mut Maze,
+    renderer: &T,
+    algorithm: AlgorithmKind,
+    layout: &super::Layout,
+    settings: &AnimationSettings,
+    mut w: W,
+    post_process: F,
+) -> io::Result<()> {
+    let width = layout.geometry.width as u32;
+    let height = layout.geometry.height as u32;
+
+    let settings = &settings.sampled(settings.interval(|| count_steps(maze, algorithm)));
+    let mut frames: Vec<u8> = Vec::new();
+    generate_frames(
+        maze,
+        renderer,
+        algorithm,
+        layout,
+        settings,
+        post_process,
+        |img, delay| {
+            let mut still = Vec::new();
+            image_webp::WebPEncoder::new(&mut still)
+                .encode(img.as_raw(), width, height, image_webp::ColorType::Rgb8)
+                .map_err(io::Error::other)?;
+            /* delays are in milliseconds, on 24 bits */
+            let delay = delay.as_millis().min(0xff_ffff) as u32;
+            let mut anmf = Vec::new();
+            anmf.extend_from_slice(&[0; 6]); /* position of the frame */
+            anmf.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+            anmf.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+            anmf.extend_from_slice(&delay.to_le_bytes()[..3]);
+            anmf.push(0b10); /* do not blend with the previous frame */
+            /* skip the RIFF header of the still image, keep its VP8L chunk */
+            anmf.extend_from_slice(&still[12..]);
+            write_riff_chunk(&mut frames, b"ANMF", &anmf);
+            Ok(())
+        },
+    )?;
+
+    let mut vp8x = vec![0b10, 0, 0, 0]; /* animation flag */
+    vp8x.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+    vp8x.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+    /* background color, then number of loops with 0 looping forever */
+    let nb_loops: u16 = if settings.loop_forever { 0 } else { 1 };
+    let mut anim = vec![0, 0, 0, 0];
+    anim.extend_from_slice(&nb_loops.to_le_bytes());
+
+    let mut webp = b"WEBP".to_vec();
+    write_riff_chunk(&mut webp, b"VP8X", &vp8x);
+    write_riff_chunk(&mut webp, b"ANIM", &anim);
+    webp.extend_from_slice(&frames);
+    w.write_all(b"RIFF")?;
+    w.write_all(&(webp.len() as u32).to_le_bytes())?;
+    w.write_all(&webp)?;
+    w.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn generated(seed: u64, algorithm: AlgorithmKind) -> Maze {
+        let mut maze = Maze::new(
+            &super::super::Geometry {
+                width: 61,
+                height: 41,
+            },
+            0.5,
+            &super::super::Origin { x: 0.0, y: 0.0 },
+            seed,
+        );
+        generate(&mut maze, algorithm, |_| {});
+        maze
+    }
 
-    if !animation {
-        if let Some(Gradient::Solution) = gradient {
-            maze.compute_solution();
+    #[test]
+    fn kruskal_distances() {
+        for seed in 0..20 {
+            let maze = generated(seed, AlgorithmKind::Kruskal);
+            let distances = maze.distances(std::slice::from_ref(&maze.origin));
+            let end = &maze.end;
+            assert_eq!(
+                distances[end.y * maze.geometry.width + end.x],
+                Some(maze.len as u32)
+            );
+            for c in maze.cells() {
+                assert!(distances[c.y * maze.geometry.width + c.x].is_some());
+            }
+            assert_eq!(maze.solution().len() as f64, maze.len + 1.0);
+        }
+    }
+
+    #[test]
+    fn growing_tree_without_weight() {
+        let zero = GrowingTreePolicy {
+            newest: 0,
+            oldest: 0,
+            random: 0,
+            middle: 0,
+        };
+        let maze = generated(3, AlgorithmKind::GrowingTree(zero));
+        let newest = generated(3, AlgorithmKind::GrowingTree(Default::default()));
+        assert_eq!(format!("{:?}", maze.grid), format!("{:?}", newest.grid));
+    }
+
+    #[test]
+    fn apng_delays() {
+        assert_eq!(apng_delay(Duration::from_millis(40)), (40, 1000));
+        assert_eq!(apng_delay(Duration::from_millis(65535)), (65535, 1000));
+        assert_eq!(apng_delay(Duration::from_secs(120)), (12000, 100));
+        assert_eq!(apng_delay(Duration::from_secs(3600)), (36000, 10));
+        assert_eq!(apng_delay(Duration::from_secs(100000)), (65535, 1));
+    }
+
+    fn dead_ends(maze: &Maze) -> usize {
+        maze.cells().iter().filter(|c| maze.is_dead_end(c)).count()
+    }
+
+    #[test]
+    fn braid_nothing() {
+        let mut maze = generated(7, AlgorithmKind::Backtracker);
+        let before = dead_ends(&maze);
+        maze.braid(0.0);
+        assert_eq!(dead_ends(&maze), before);
+    }
+
+    #[test]
+    fn braid_half() {
+        for seed in 0..10 {
+            let mut maze = generated(seed, AlgorithmKind::Backtracker);
+            let before = dead_ends(&maze);
+            maze.braid(0.5);
+            assert_eq!(dead_ends(&maze), before - before.div_ceil(2));
         }
+    }
 
-        let img = maze.draw(renderer);
-        let _ = img.save(path);
+    #[test]
+    fn braid_all() {
+        let mut maze = generated(7, AlgorithmKind::Backtracker);
+        assert!(dead_ends(&maze) > 0);
+        maze.braid(1.0);
+        assert_eq!(dead_ends(&maze), 0);
+        for c in maze.cells() {
+            match maze.cell_kind(&c) {
+                CellKind::PathKind(f) => assert!((0.0..=1.0).contains(&f)),
+                kind => panic!("cell {:?} is {:?}", c, kind),
+            }
+        }
+        assert_eq!(maze.solution().len() as f64, maze.len() + 1.0);
     }
 }
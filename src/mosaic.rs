@@ -1,8 +1,13 @@
 use crate::maze::{CellKind, Coord, Maze, Rendering};
+use crate::plain::draw_cell_plain_gif;
 use image::{Rgb, RgbImage};
 use rand::distr::{Distribution, Uniform};
+use rand::rngs::StdRng;
 
-const TILE_SIZE: u32 = 5;
+const PATTERN_SIZE: u32 = 5;
+
+/// Default size in pixels of the tiles.
+pub const TILE_SIZE: usize = PATTERN_SIZE as usize;
 
 const TILES_DARK: [[Rgb<u8>; 3]; 5] = [
     [Rgb([31, 31, 31]), Rgb([112, 112, 122]), Rgb([92, 92, 92])],
@@ -40,102 +45,97 @@ const TILES_LIGHT: [[Rgb<u8>; 3]; 5] = [
     ],
 ];
 
-fn draw_tile(img: &mut RgbImage, x: u32, y: u32, tile: &[Rgb<u8>; 3]) {
-    img.put_pixel(x, y, tile[0]);
-    img.put_pixel(x, y + 1, tile[0]);
-    img.put_pixel(x, y + 2, tile[0]);
-    img.put_pixel(x, y + 3, tile[0]);
-    img.put_pixel(x, y + 4, tile[0]);
-    img.put_pixel(x + 1, y, tile[0]);
-    img.put_pixel(x + 2, y, tile[0]);
-    img.put_pixel(x + 3, y, tile[0]);
-    img.put_pixel(x + 4, y, tile[0]);
+const TILE_SETS: [[[Rgb<u8>; 3]; 5]; 2] = [TILES_DARK, TILES_LIGHT];
 
-    img.put_pixel(x + 1, y + 1, tile[1]);
-    img.put_pixel(x + 1, y + 2, tile[1]);
-    img.put_pixel(x + 1, y + 3, tile[1]);
-    img.put_pixel(x + 1, y + 4, tile[1]);
-    img.put_pixel(x + 2, y + 1, tile[1]);
-    img.put_pixel(x + 3, y + 1, tile[1]);
-    img.put_pixel(x + 4, y + 1, tile[1]);
-    img.put_pixel(x + 2, y + 4, tile[1]);
-    img.put_pixel(x + 3, y + 4, tile[1]);
-    img.put_pixel(x + 4, y + 2, tile[1]);
-    img.put_pixel(x + 4, y + 3, tile[1]);
-    img.put_pixel(x + 4, y + 4, tile[1]);
+/* The gif palette starts with the wall and undefined colors, followed for
+ * each set of tiles by the color of the joints and the shades of the tiles */
+const GIF_FIRST_TILE_INDEX: u8 = 2;
 
-    img.put_pixel(x + 2, y + 2, tile[1]);
-    img.put_pixel(x + 2, y + 3, tile[1]);
-    img.put_pixel(x + 3, y + 2, tile[1]);
-    img.put_pixel(x + 3, y + 3, tile[1]);
+/* Tiles are drawn at any size by scaling the 5x5 pattern they were designed
+ * on: a one pixel joint in the first color on the top and left edges, and
+ * the second color inside. `put` is called with the position of every pixel
+ * of the tile and whether it belongs to the joint. */
+fn tile_pixels<F: FnMut(u32, u32, bool)>(x: u32, y: u32, size: u32, mut put: F) {
+    for i in 0..size {
+        for j in 0..size {
+            let (pi, pj) = (i * PATTERN_SIZE / size, j * PATTERN_SIZE / size);
+            put(x + i, y + j, pi == 0 || pj == 0);
+        }
+    }
 }
 
-fn draw_tile_rand(img: &mut RgbImage, x: u32, y: u32, tiles: &[[Rgb<u8>; 3]; 5]) {
+fn tile_rand(rng: &mut StdRng) -> usize {
     let between: Uniform<u8> =
-        Uniform::new(0, tiles.len() as u8).expect("cannot create uniform random distribution");
-    let mut rng = rand::rng();
-    let idx = between.sample(&mut rng) as usize;
-    draw_tile(img, x, y, &tiles[idx]);
+        Uniform::new(0, 5).expect("cannot create uniform random distribution");
+    between.sample(rng) as usize
 }
 
 pub struct RendererMosaic {
     pub is_inverted: bool,
+    pub tile_size: usize,
+}
+
+impl RendererMosaic {
+    /* Index in TILE_SETS of the tiles of a cell */
+    fn tile_set(&self, cell_kind: &CellKind) -> Option<usize> {
+        match *cell_kind {
+            CellKind::PathKind(_) => Some(if self.is_inverted { 0 } else { 1 }),
+            CellKind::WallKind => Some(if self.is_inverted { 1 } else { 0 }),
+            CellKind::Undefined => None,
+        }
+    }
 }
 
 impl Rendering for RendererMosaic {
     fn tile_size(&self) -> usize {
-        TILE_SIZE as usize
+        self.tile_size
     }
-    fn draw_cell(&self, _maze: &Maze, img: &mut RgbImage, c: &Coord, cell_kind: CellKind) {
-        match cell_kind {
-            CellKind::PathKind(_) => {
-                if self.is_inverted {
-                    draw_tile_rand(
-                        img,
-                        c.x as u32 * TILE_SIZE,
-                        c.y as u32 * TILE_SIZE,
-                        &TILES_DARK,
-                    );
-                } else {
-                    draw_tile_rand(
-                        img,
-                        c.x as u32 * TILE_SIZE,
-                        c.y as u32 * TILE_SIZE,
-                        &TILES_LIGHT,
-                    );
-                }
-            }
-            CellKind::WallKind => {
-                if self.is_inverted {
-                    draw_tile_rand(
-                        img,
-                        c.x as u32 * TILE_SIZE,
-                        c.y as u32 * TILE_SIZE,
-                        &TILES_LIGHT,
-                    );
-                } else {
-                    draw_tile_rand(
-                        img,
-                        c.x as u32 * TILE_SIZE,
-                        c.y as u32 * TILE_SIZE,
-                        &TILES_DARK,
-                    );
-                }
-            }
-            _ => {}
+    fn draw_cell(&self, maze: &Maze, img: &mut RgbImage, c: &Coord, cell_kind: CellKind) {
+        if let Some(set) = self.tile_set(&cell_kind) {
+            let tile = &TILE_SETS[set][tile_rand(&mut maze.cell_rng(c))];
+            let size = self.tile_size as u32;
+            tile_pixels(c.x as u32 * size, c.y as u32 * size, size, |x, y, joint| {
+                img.put_pixel(x, y, if joint { tile[0] } else { tile[1] });
+            });
         }
     }
     fn draw_cell_gif(
         &self,
-        _maze: &Maze,
-        _img_geom: &super::Geometry,
-        _buffer: &mut Vec<u8>,
-        _c: &Coord,
-        _cell_kind: CellKind,
+        maze: &Maze,
+        img_geom: &super::Geometry,
+        buffer: &mut Vec<u8>,
+        c: &Coord,
+        cell_kind: CellKind,
     ) {
-        unimplemented!();
+        match self.tile_set(&cell_kind) {
+            Some(set) => {
+                let first = GIF_FIRST_TILE_INDEX + 6 * set as u8;
+                let shade = first + 1 + tile_rand(&mut maze.cell_rng(c)) as u8;
+                let size = self.tile_size as u32;
+                tile_pixels(c.x as u32 * size, c.y as u32 * size, size, |x, y, joint| {
+                    buffer[x as usize + y as usize * img_geom.width] =
+                        if joint { first } else { shade };
+                });
+            }
+            None => draw_cell_plain_gif(self, img_geom.width, buffer, c, 1),
+        }
     }
     fn get_gif_palette(&self) -> Vec<u8> {
-        unimplemented!();
+        let mut palette = Vec::new();
+        palette.extend_from_slice(&self.cell_color(&CellKind::WallKind).0);
+        palette.extend_from_slice(&self.cell_color(&CellKind::Undefined).0);
+        for tiles in TILE_SETS.iter() {
+            palette.extend_from_slice(&tiles[0][0].0);
+            for tile in tiles.iter() {
+                palette.extend_from_slice(&tile[1].0);
+            }
+        }
+        palette
+    }
+    fn cell_color(&self, cell_kind: &CellKind) -> Rgb<u8> {
+        match self.tile_set(cell_kind) {
+            Some(set) => TILE_SETS[set][2][1],
+            None => Rgb([0, 0, 0]),
+        }
     }
 }
@@ -2,6 +2,9 @@ use crate::maze::{CellKind, Coord, Maze, Rendering};
 use color_scaling::scale_rgb;
 use image::{Rgb, RgbImage};
 
+/// Default size in pixels of the tiles.
+pub const TILE_SIZE: usize = 4;
+
 pub fn draw_cell_plain<T: Rendering>(renderer: &T, img: &mut RgbImage, c: &Coord, p: &Rgb<u8>) {
     let tile_size = renderer.tile_size();
     for i in 0..tile_size {
@@ -34,22 +37,50 @@ pub struct RendererPlain {
     pub path_color_start: Rgb<u8>,
     pub path_color_end: Rgb<u8>,
     pub wall_color: Rgb<u8>,
+    pub tile_size: usize,
 }
-impl Rendering for RendererPlain {
-    fn tile_size(&self) -> usize {
-        4
+/* The gif palette starts with the wall and undefined colors, followed by the
+ * steps of the gradient between the path colors. The last of the 256 colors
+ * is left for the transparent color of animations. */
+const GIF_FIRST_PATH_INDEX: u8 = 2;
+const GIF_GRADIENT_STEPS: usize = 253;
+
+impl RendererPlain {
+    fn gif_gradient_steps(&self) -> usize {
+        if self.path_color_start == self.path_color_end {
+            1
+        } else {
+            GIF_GRADIENT_STEPS
+        }
     }
-    fn draw_cell(&self, _maze: &Maze, img: &mut RgbImage, c: &Coord, cell_kind: CellKind) {
-        match cell_kind {
+
+    /// Index in the gif palette of the color of a cell. Paths get the step
+    /// of the gradient nearest to their value.
+    pub fn gif_color_index(&self, cell_kind: &CellKind) -> u8 {
+        match *cell_kind {
             CellKind::PathKind(f) => {
-                let color = scale_rgb(&self.path_color_start, &self.path_color_end, f).unwrap();
-                draw_cell_plain(self, img, c, &color);
-            }
-            _ => {
-                draw_cell_plain(self, img, c, &self.wall_color);
+                let f = if f.is_nan() {
+                    0_f64
+                } else {
+                    f.clamp(0_f64, 1_f64)
+                };
+                let step = (f * (self.gif_gradient_steps() - 1) as f64).round() as u8;
+                GIF_FIRST_PATH_INDEX + step
             }
+            CellKind::WallKind => 0,
+            CellKind::Undefined => 1,
         }
     }
+}
+
+impl Rendering for RendererPlain {
+    fn tile_size(&self) -> usize {
+        self.tile_size
+    }
+    fn draw_cell(&self, _maze: &Maze, img: &mut RgbImage, c: &Coord, cell_kind: CellKind) {
+        let color = self.cell_color(&cell_kind);
+        draw_cell_plain(self, img, c, &color);
+    }
     fn draw_cell_gif(
         &self,
         _maze: &Maze,
@@ -58,34 +89,26 @@ impl Rendering for RendererPlain {
         c: &Coord,
         cell_kind: CellKind,
     ) {
-        match cell_kind {
-            CellKind::PathKind(f) => {
-                if self.path_color_start != self.path_color_end
-                    && !f.is_nan()
-                    && (0f64..1f64).contains(&f)
-                {
-                    panic!("not implemented");
-                } else {
-                    draw_cell_plain_gif(self, img_geom.width, buffer, c, 1);
-                }
-            }
-            _ => {
-                draw_cell_plain_gif(self, img_geom.width, buffer, c, 0);
-            }
-        }
+        let color_idx = self.gif_color_index(&cell_kind);
+        draw_cell_plain_gif(self, img_geom.width, buffer, c, color_idx);
     }
     fn get_gif_palette(&self) -> Vec<u8> {
-        if self.path_color_start != self.path_color_end {
-            panic!("not implemented");
-        } else {
-            vec![
-                self.wall_color[0],
-                self.wall_color[1],
-                self.wall_color[2],
-                self.path_color_start[0],
-                self.path_color_start[1],
-                self.path_color_start[2],
-            ]
+        let steps = self.gif_gradient_steps();
+        let mut palette = Vec::with_capacity(3 * (2 + steps));
+        palette.extend_from_slice(&self.wall_color.0);
+        palette.extend_from_slice(&self.cell_color(&CellKind::Undefined).0);
+        for i in 0..steps {
+            let f = i as f64 / (steps - 1).max(1) as f64;
+            palette.extend_from_slice(&self.cell_color(&CellKind::PathKind(f)).0);
+        }
+        palette
+    }
+    fn cell_color(&self, cell_kind: &CellKind) -> Rgb<u8> {
+        match *cell_kind {
+            CellKind::PathKind(f) => {
+                scale_rgb(&self.path_color_start, &self.path_color_end, f).unwrap()
+            }
+            _ => self.wall_color,
         }
     }
 }
@@ -0,0 +1,656 @@
+//! Algorithms finding the way from the origin to the end of a [`Maze`].
+//!
+//! Solvers walk the paths of the grid, cells and the openings between them
+//! alike, one step at a time like the generation algorithms do.
+
+use crate::maze::{opposite, CellKind, Coord, Direction, Maze};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, VecDeque};
+use std::time::{Duration, Instant};
+
+/* Grid {{{ */
+
+fn index(maze: &Maze, c: &Coord) -> usize {
+    c.y * maze.geometry().width + c.x
+}
+
+fn coord(maze: &Maze, i: usize) -> Coord {
+    let width = maze.geometry().width;
+    Coord {
+        x: i % width,
+        y: i / width,
+    }
+}
+
+fn neighbours<'a>(maze: &'a Maze, c: &'a Coord) -> impl Iterator<Item = Coord> + 'a {
+    Direction::CLOCKWISE
+        .iter()
+        .filter_map(move |dir| maze.path_next(c, dir))
+}
+
+/* Path from the origin to `end` following `parents`, indexed like the grid,
+ * the origin being its own parent */
+fn backtrack(maze: &Maze, parents: &[Option<usize>], end: usize) -> Vec<Coord> {
+    let mut path = vec![coord(maze, end)];
+    let mut i = end;
+    while let Some(p) = parents[i] {
+        if p == i {
+            break;
+        }
+        path.push(coord(maze, p));
+        i = p;
+    }
+    path.reverse();
+    path
+}
+
+/* }}} */
+
+pub trait Solver {
+    /// Explore one more step, returning the cell reached, or `None` once
+    /// the search is over.
+    fn next(&mut self) -> Option<Coord>;
+    /// Path found from the origin to the end, both included. Empty if the
+    /// end was not reached.
+    fn path(&self) -> Vec<Coord>;
+}
+
+/* Wall follower {{{ */
+/*
+ * Keep a hand on a wall and walk along it: at each step, turn towards that
+ * hand if possible, else go straight, else turn the other way, else go back.
+ *
+ * It only finds the end when both the origin and the end are on walls
+ * connected to each other, which is always the case in a perfect maze. In a
+ * maze with loops it may walk in circles: the walk gives up when it comes
+ * back to a cell facing the same direction. The path is the walk with its
+ * loops erased.
+ */
+struct WallFollower<'a> {
+    maze: &'a Maze,
+    /* turn towards the hand, as a number of right turns */
+    hand: usize,
+    dir: Direction,
+    path: Vec<Coord>,
+    path_index: Vec<Option<usize>>,
+    seen: Vec<bool>,
+    found: bool,
+    done: bool,
+}
+
+impl<'a> WallFollower<'a> {
+    fn init(maze: &'a Maze, left_hand: bool) -> WallFollower<'a> {
+        let len = maze.geometry().width * maze.geometry().height;
+        WallFollower {
+            maze,
+            hand: if left_hand { 3 } else { 1 },
+            dir: Direction::Up,
+            path: Vec::new(),
+            path_index: vec![None; len],
+            seen: vec![false; 4 * len],
+            found: false,
+            done: false,
+        }
+    }
+
+    fn push(&mut self, c: Coord) {
+        let i = index(self.maze, &c);
+        if let Some(p) = self.path_index[i] {
+            /* back on the path: erase the loop */
+            for erased in self.path.drain(p + 1..) {
+                self.path_index[index(self.maze, &erased)] = None;
+            }
+        } else {
+            self.path_index[i] = Some(self.path.len());
+            self.path.push(c);
+        }
+    }
+}
+
+impl Solver for WallFollower<'_> {
+    fn next(&mut self) -> Option<Coord> {
+        if self.done {
+            return None;
+        }
+        let c = match self.path.last() {
+            None => {
+                let origin = self.maze.origin();
+                self.push(origin.clone());
+                return Some(origin);
+            }
+            Some(c) => c.clone(),
+        };
+        if index(self.maze, &c) == index(self.maze, &self.maze.end()) {
+            self.found = true;
+            self.done = true;
+            return None;
+        }
+        let state = 4 * index(self.maze, &c) + self.dir.quarters();
+        if self.seen[state] {
+            self.done = true;
+            return None;
+        }
+        self.seen[state] = true;
+        for turn in [self.hand, 0, 4 - self.hand, 2] {
+            let dir = self.dir.turn(turn);
+            if let Some(n) = self.maze.path_next(&c, &dir) {
+                self.dir = dir;
+                self.push(n.clone());
+                return Some(n);
+            }
+        }
+        /* nowhere to go */
+        self.done = true;
+        None
+    }
+
+    fn path(&self) -> Vec<Coord> {
+        if self.found {
+            self.path.clone()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/* }}} */
+/* Trémaux {{{ */
+/*
+ * Mark passages when walking through them: once when entering, twice when
+ * walking back out of them. A passage marked twice is never taken again.
+ *
+ * 1. Reaching an already visited place through a passage marked once, turn
+ *    back right away, as in a dead end.
+ * 2. Else take the passage with the fewest marks: a passage that is not
+ *    marked yet, if any, or the one marked once that leads back.
+ *
+ * The passages marked once form the path from the origin, kept as a stack.
+ * It works in mazes with loops, as the loops get marked twice.
+ */
+struct Tremaux<'a> {
+    maze: &'a Maze,
+    stack: Vec<Coord>,
+    /* marks of the passages, in each direction from each place */
+    marks: Vec<u8>,
+    /* direction of the last move */
+    dir: Option<Direction>,
+    found: bool,
+    done: bool,
+}
+
+impl<'a> Tremaux<'a> {
+    fn init(maze: &'a Maze) -> Tremaux<'a> {
+        Tremaux {
+            maze,
+            stack: Vec::new(),
+            marks: vec![0; 4 * maze.geometry().width * maze.geometry().height],
+            dir: None,
+            found: false,
+            done: false,
+        }
+    }
+
+    fn mark(&self, c: &Coord, dir: &Direction) -> u8 {
+        self.marks[4 * index(self.maze, c) + dir.quarters()]
+    }
+}
+
+impl Solver for Tremaux<'_> {
+    fn next(&mut self) -> Option<Coord> {
+        if self.done {
+            return None;
+        }
+        let c = match self.stack.last() {
+            None => {
+                let origin = self.maze.origin();
+                self.stack.push(origin.clone());
+                return Some(origin);
+            }
+            Some(c) => c.clone(),
+        };
+        if index(self.maze, &c) == index(self.maze, &self.maze.end()) {
+            self.found = true;
+            self.done = true;
+            return None;
+        }
+        let open: Vec<Direction> = Direction::CLOCKWISE
+            .into_iter()
+            .filter(|dir| self.maze.path_next(&c, dir).is_some())
+            .collect();
+        let back = self.dir.map(|dir| opposite(&dir));
+        let visited = open
+            .iter()
+            .any(|dir| Some(*dir) != back && self.mark(&c, dir) > 0);
+        let dir = match back {
+            Some(back) if visited && self.mark(&c, &back) == 1 => Some(back),
+            _ => open
+                .into_iter()
+                .filter(|dir| self.mark(&c, dir) < 2)
+                .min_by_key(|dir| self.mark(&c, dir)),
+        };
+        let Some(dir) = dir else {
+            /* every passage was walked twice: the end can not be reached */
+            self.done = true;
+            return None;
+        };
+        let n = self.maze.path_next(&c, &dir).unwrap();
+        if self.mark(&c, &dir) == 0 {
+            self.stack.push(n.clone());
+        } else {
+            self.stack.pop();
+        }
+        self.marks[4 * index(self.maze, &c) + dir.quarters()] += 1;
+        self.marks[4 * index(self.maze, &n) + opposite(&dir).quarters()] += 1;
+        self.dir = Some(dir);
+        Some(n)
+    }
+
+    fn path(&self) -> Vec<Coord> {
+        if self.found {
+            self.stack.clone()
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/* }}} */
+/* Dead-end filling {{{ */
+/*
+ * 1. Find all the dead ends of the maze, other than the origin and the end.
+ * 2. Fill each of them, and the corridor leading to it, until reaching a
+ *    junction.
+ * 3. What is left is the solution, with the loops of the maze if it has
+ *    some: walk the shortest way through it.
+ *
+ * It looks at the whole maze rather than walking in it.
+ */
+struct DeadEndFilling<'a> {
+    maze: &'a Maze,
+    filled: Vec<bool>,
+    dead_ends: Vec<Coord>,
+    path: Option<Vec<Coord>>,
+    traced: usize,
+}
+
+impl<'a> DeadEndFilling<'a> {
+    fn init(maze: &'a Maze) -> DeadEndFilling<'a> {
+        let g = maze.geometry();
+        let mut s = DeadEndFilling {
+            maze,
+            filled: vec![false; g.width * g.height],
+            dead_ends: Vec::new(),
+            path: None,
+            traced: 0,
+        };
+        for y in 0..g.height {
+            for x in 0..g.width {
+                let c = Coord { x, y };
+                if let CellKind::PathKind(_) = maze.cell_kind(&c) {
+                    if s.is_dead_end(&c) {
+                        s.dead_ends.push(c);
+                    }
+                }
+            }
+        }
+        s.dead_ends.reverse();
+        s
+    }
+
+    fn open_neighbours(&self, c: &Coord) -> Vec<Coord> {
+        neighbours(self.maze, c)
+            .filter(|n| !self.filled[index(self.maze, n)])
+            .collect()
+    }
+
+    fn is_dead_end(&self, c: &Coord) -> bool {
+        let i = index(self.maze, c);
+        !self.filled[i]
+            && i != index(self.maze, &self.maze.origin())
+            && i != index(self.maze, &self.maze.end())
+            && self.open_neighbours(c).len() <= 1
+    }
+}
+
+impl Solver for DeadEndFilling<'_> {
+    fn next(&mut self) -> Option<Coord> {
+        while let Some(c) = self.dead_ends.pop() {
+            if !self.is_dead_end(&c) {
+                continue;
+            }
+            self.filled[index(self.maze, &c)] = true;
+            for n in self.open_neighbours(&c) {
+                if self.is_dead_end(&n) {
+                    self.dead_ends.push(n);
+                }
+            }
+            return Some(c);
+        }
+        let (maze, filled) = (self.maze, &self.filled);
+        let path = self
+            .path
+            .get_or_insert_with(|| maze.solution_through(|c| !filled[index(maze, c)]));
+        let c = path.get(self.traced)?.clone();
+        self.traced += 1;
+        Some(c)
+    }
+
+    fn path(&self) -> Vec<Coord> {
+        self.path.clone().unwrap_or_default()
+    }
+}
+
+/* }}} */
+/* Breadth-first search {{{ */
+/*
+ * Explore the cells in the order of their distance to the origin, with a
+ * queue, remembering from which cell each one was reached. The path is
+ * walked back from the end: it is always a shortest one.
+ */
+struct BreadthFirst<'a> {
+    maze: &'a Maze,
+    queue: VecDeque<Coord>,
+    parents: Vec<Option<usize>>,
+    found: bool,
+}
+
+impl<'a> BreadthFirst<'a> {
+    fn init(maze: &'a Maze) -> BreadthFirst<'a> {
+        let origin = maze.origin();
+        let mut parents = vec![None; maze.geometry().width * maze.geometry().height];
+        let mut queue = VecDeque::new();
+        if let CellKind::PathKind(_) = maze.cell_kind(&origin) {
+            parents[index(maze, &origin)] = Some(index(maze, &origin));
+            queue.push_back(origin);
+        }
+        BreadthFirst {
+            maze,
+            queue,
+            parents,
+            found: false,
+        }
+    }
+}
+
+impl Solver for BreadthFirst<'_> {
+    fn next(&mut self) -> Option<Coord> {
+        if self.found {
+            return None;
+        }
+        let c = self.queue.pop_front()?;
+        let i = index(self.maze, &c);
+        if i == index(self.maze, &self.maze.end()) {
+            self.found = true;
+            return Some(c);
+        }
+        for n in neighbours(self.maze, &c) {
+            let j = index(self.maze, &n);
+            if self.parents[j].is_none() {
+                self.parents[j] = Some(i);
+                self.queue.push_back(n);
+            }
+        }
+        Some(c)
+    }
+
+    fn path(&self) -> Vec<Coord> {
+        if self.found {
+            backtrack(self.maze, &self.parents, index(self.maze, &self.maze.end()))
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/* }}} */
+/* A* {{{ */
+/*
+ * Like the breadth-first search, but explore first the cells with the
+ * smallest distance from the origin plus estimated distance to the end. The
+ * estimate is the Manhattan distance, which never overestimates, so the path
+ * found is a shortest one. Among equal cells, those closer to the end come
+ * first.
+ */
+struct AStar<'a> {
+    maze: &'a Maze,
+    /* (estimated length, estimated remaining distance, cell) */
+    open: BinaryHeap<Reverse<(usize, usize, usize)>>,
+    distances: Vec<Option<usize>>,
+    parents: Vec<Option<usize>>,
+    closed: Vec<bool>,
+    found: bool,
+}
+
+impl<'a> AStar<'a> {
+    fn init(maze: &'a Maze) -> AStar<'a> {
+        let len = maze.geometry().width * maze.geometry().height;
+        let mut s = AStar {
+            maze,
+            open: BinaryHeap::new(),
+            distances: vec![None; len],
+            parents: vec![None; len],
+            closed: vec![false; len],
+            found: false,
+        };
+        let origin = maze.origin();
+        if let CellKind::PathKind(_) = maze.cell_kind(&origin) {
+            let i = index(maze, &origin);
+            s.distances[i] = Some(0);
+            s.parents[i] = Some(i);
+            let h = s.estimate(&origin);
+            s.open.push(Reverse((h, h, i)));
+        }
+        s
+    }
+
+    fn estimate(&self, c: &Coord) -> usize {
+        let end = self.maze.end();
+        c.x.abs_diff(end.x) + c.y.abs_diff(end.y)
+    }
+}
+
+impl Solver for AStar<'_> {
+    fn next(&mut self) -> Option<Coord> {
+        if self.found {
+            return None;
+        }
+        loop {
+            let Reverse((_, _, i)) = self.open.pop()?;
+            if self.closed[i] {
+                continue;
+            }
+            self.closed[i] = true;
+            let c = coord(self.maze, i);
+            if i == index(self.maze, &self.maze.end()) {
+                self.found = true;
+                return Some(c);
+            }
+            let d = self.distances[i].unwrap() + 1;
+            for n in neighbours(self.maze, &c) {
+                let j = index(self.maze, &n);
+                if self.distances[j].is_none_or(|dj| d < dj) {
+                    self.distances[j] = Some(d);
+                    self.parents[j] = Some(i);
+                    let h = self.estimate(&n);
+                    self.open.push(Reverse((d + h, h, j)));
+                }
+            }
+            return Some(c);
+        }
+    }
+
+    fn path(&self) -> Vec<Coord> {
+        if self.found {
+            backtrack(self.maze, &self.parents, index(self.maze, &self.maze.end()))
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/* }}} */
+
+#[derive(Debug, Clone, Copy)]
+pub enum SolverKind {
+    WallFollowerLeft,
+    WallFollowerRight,
+    Tremaux,
+    DeadEndFilling,
+    BreadthFirst,
+    AStar,
+}
+
+impl SolverKind {
+    pub const ALL: [SolverKind; 6] = [
+        SolverKind::WallFollowerLeft,
+        SolverKind::WallFollowerRight,
+        SolverKind::Tremaux,
+        SolverKind::DeadEndFilling,
+        SolverKind::BreadthFirst,
+        SolverKind::AStar,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SolverKind::WallFollowerLeft => "wall-follower-left",
+            SolverKind::WallFollowerRight => "wall-follower-right",
+            SolverKind::Tremaux => "tremaux",
+            SolverKind::DeadEndFilling => "dead-end-filling",
+            SolverKind::BreadthFirst => "bfs",
+            SolverKind::AStar => "a-star",
+        }
+    }
+}
+
+/// Solver of the given kind, at the origin of `maze`.
+pub fn solver<'a>(maze: &'a Maze, kind: SolverKind) -> Box<dyn Solver + 'a> {
+    match kind {
+        SolverKind::WallFollowerLeft => Box::new(WallFollower::init(maze, true)),
+        SolverKind::WallFollowerRight => Box::new(WallFollower::init(maze, false)),
+        SolverKind::Tremaux => Box::new(Tremaux::init(maze)),
+        SolverKind::DeadEndFilling => Box::new(DeadEndFilling::init(maze)),
+        SolverKind::BreadthFirst => Box::new(BreadthFirst::init(maze)),
+        SolverKind::AStar => Box::new(AStar::init(maze)),
+    }
+}
+
+/// Outcome of a solver on a maze.
+#[derive(Debug, Clone)]
+pub struct SolveReport {
+    pub kind: SolverKind,
+    /// Path from the origin to the end, both included. Empty if the solver
+    /// did not find the end.
+    pub path: Vec<Coord>,
+    /// Number of steps taken by the solver.
+    pub steps: usize,
+    /// Number of distinct cells the solver went through.
+    pub visited: usize,
+    pub time: Duration,
+}
+
+/// Run a solver of the given kind on `maze` until it is over, calling
+/// `on_step` with every cell it reaches.
+pub fn solve<F: FnMut(&Coord)>(maze: &Maze, kind: SolverKind, mut on_step: F) -> SolveReport {
+    let g = maze.geometry();
+    let mut visited = vec![false; g.width * g.height];
+    let mut nb_visited = 0;
+    let mut steps = 0;
+    let start = Instant::now();
+    let mut s = solver(maze, kind);
+    while let Some(c) = s.next() {
+        steps += 1;
+        let i = index(maze, &c);
+        if !visited[i] {
+            visited[i] = true;
+            nb_visited += 1;
+        }
+        on_step(&c);
+    }
+    let path = s.path();
+    SolveReport {
+        kind,
+        path,
+        steps,
+        visited: nb_visited,
+        time: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AlgorithmKind, Geometry, MazeBuilder, Origin};
+
+    fn maze(braid: f64) -> Maze {
+        MazeBuilder::new()
+            .geometry(Geometry {
+                width: 244,
+                height: 164,
+            })
+            .algorithm(AlgorithmKind::Backtracker)
+            .origin(Origin { x: 0.5, y: 0.5 })
+            .braid(braid)
+            .seed(11)
+            .build()
+    }
+
+    /* The path goes from the origin to the end, one step at a time */
+    fn assert_walkable(maze: &Maze, path: &[Coord]) {
+        assert_eq!(index(maze, &path[0]), index(maze, &maze.origin()));
+        assert_eq!(index(maze, path.last().unwrap()), index(maze, &maze.end()));
+        for w in path.windows(2) {
+            assert_eq!(w[0].x.abs_diff(w[1].x) + w[0].y.abs_diff(w[1].y), 1);
+            assert!(matches!(maze.cell_kind(&w[1]), CellKind::PathKind(_)));
+        }
+    }
+
+    #[test]
+    fn perfect_maze() {
+        let maze = maze(0.0);
+        let solution = maze.solution().len();
+        for kind in SolverKind::ALL {
+            let report = solve(&maze, kind, |_| {});
+            assert_walkable(&maze, &report.path);
+            /* there is a single route */
+            assert_eq!(report.path.len(), solution, "{}", kind.name());
+        }
+    }
+
+    #[test]
+    fn shortest_paths() {
+        let maze = maze(0.5);
+        let solution = maze.solution().len();
+        for kind in [
+            SolverKind::DeadEndFilling,
+            SolverKind::BreadthFirst,
+            SolverKind::AStar,
+        ] {
+            let report = solve(&maze, kind, |_| {});
+            assert_walkable(&maze, &report.path);
+            assert_eq!(report.path.len(), solution, "{}", kind.name());
+        }
+        let report = solve(&maze, SolverKind::Tremaux, |_| {});
+        assert_walkable(&maze, &report.path);
+    }
+
+    #[test]
+    fn tremaux_marks() {
+        let maze = maze(0.5);
+        let mut tremaux = Tremaux::init(&maze);
+        while tremaux.next().is_some() {}
+        let path = tremaux.path();
+        assert_walkable(&maze, &path);
+        assert!(tremaux.marks.iter().all(|m| *m <= 2));
+        /* the path follows the passages marked once */
+        for w in path.windows(2) {
+            let dir = Direction::CLOCKWISE
+                .into_iter()
+                .find(|dir| {
+                    maze.path_next(&w[0], dir).map(|n| index(&maze, &n))
+                        == Some(index(&maze, &w[1]))
+                })
+                .unwrap();
+            assert_eq!(tremaux.mark(&w[0], &dir), 1);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! Measures of the shape and difficulty of a finished [`Maze`].
+
+use crate::maze::{opposite, CellKind, Coord, Direction, Maze};
+use std::fmt;
+
+/// Statistics of a maze. Counts are in cells, lengths in steps of the grid,
+/// where going from a cell to the next one takes 2 steps.
+#[derive(Debug, Clone)]
+pub struct Stats {
+    /// Number of cells of the maze.
+    pub cells: usize,
+    /// Cells with a single opening.
+    pub dead_ends: usize,
+    /// Fraction of the cells that are dead ends.
+    pub dead_end_ratio: f64,
+    /// Cells with 3 or 4 openings, where paths branch.
+    pub junctions: usize,
+    /// Cells with 2 openings, inside corridors.
+    pub corridors: usize,
+    /// Length of the shortest path from the origin to the end.
+    pub solution_length: usize,
+    /// Length of the longest of the shortest paths between two cells.
+    pub diameter: usize,
+    /// Solution length relative to the diameter.
+    pub solution_ratio: f64,
+    /// Average length of the dead end branches, from a dead end to the
+    /// first junction.
+    pub average_branch_length: f64,
+    /// Fraction of the cells that are corridors. Mazes with a high river
+    /// factor flow in long passages with few, long, dead ends.
+    pub river_factor: f64,
+    /// Corridor cells where the path turns rather than going straight.
+    pub turns: usize,
+    /// Turns along the solution.
+    pub solution_turns: usize,
+}
+
+fn ratio(n: f64, total: f64) -> f64 {
+    if total > 0_f64 {
+        n / total
+    } else {
+        0_f64
+    }
+}
+
+/* Directions in which a cell is open towards a neighbour */
+fn openings(maze: &Maze, c: &Coord) -> Vec<Direction> {
+    Direction::CLOCKWISE
+        .into_iter()
+        .filter(|dir| maze.path_next(c, dir).is_some())
+        .collect()
+}
+
+/* Length of the branch from the dead end `c`, open in the direction `dir`,
+ * to the first cell which is not in a corridor */
+fn branch_length(maze: &Maze, c: &Coord, mut dir: Direction) -> usize {
+    let mut c = c.clone();
+    let mut length = 0;
+    while let Some(n) = maze
+        .path_next(&c, &dir)
+        .and_then(|n| maze.path_next(&n, &dir))
+    {
+        c = n;
+        length += 2;
+        let open = openings(maze, &c);
+        if open.len() != 2 {
+            break;
+        }
+        /* leave by the opening we did not come from */
+        let back = opposite(&dir);
+        dir = if open[0] == back { open[1] } else { open[0] };
+    }
+    length
+}
+
+/* Distance from `c` to the furthest cell, and that cell */
+fn eccentricity(maze: &Maze, c: &Coord) -> (usize, Coord) {
+    let width = maze.geometry().width;
+    let (i, d) = maze
+        .distances(std::slice::from_ref(c))
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| (i % width).is_multiple_of(2) && (i / width).is_multiple_of(2))
+        .filter_map(|(i, d)| d.map(|d| (i, d as usize)))
+        .max_by_key(|(_, d)| *d)
+        .unwrap_or((c.y * width + c.x, 0));
+    (
+        d,
+        Coord {
+            x: i % width,
+            y: i / width,
+        },
+    )
+}
+
+/* In a perfect maze, the furthest cell from any cell is at an end of a
+ * longest path, whose other end is the furthest cell from it. With loops,
+ * every cell has to be tried. */
+fn diameter(maze: &Maze, cells: &[Coord], is_perfect: bool) -> usize {
+    match cells.first() {
+        None => 0,
+        Some(c) if is_perfect => eccentricity(maze, &eccentricity(maze, c).1).0,
+        Some(_) => cells
+            .iter()
+            .map(|c| eccentricity(maze, c).0)
+            .max()
+            .unwrap_or(0),
+    }
+}
+
+/// Analyse a finished maze.
+pub fn stats(maze: &Maze) -> Stats {
+    let g = maze.geometry();
+    let mut cells = 0;
+    let mut dead_ends = 0;
+    let mut junctions = 0;
+    let mut corridors = 0;
+    let mut turns = 0;
+    let mut branches = 0;
+    let mut nb_openings = 0;
+    let mut path_cells = Vec::new();
+    for y in (0..g.height).step_by(2) {
+        for x in (0..g.width).step_by(2) {
+            let c = Coord { x, y };
+            if !matches!(maze.cell_kind(&c), CellKind::PathKind(_)) {
+                continue;
+            }
+            cells += 1;
+            let open = openings(maze, &c);
+            nb_openings += open.len();
+            match open.len() {
+                1 => {
+                    dead_ends += 1;
+                    branches += branch_length(maze, &c, open[0]);
+                }
+                2 => {
+                    corridors += 1;
+                    if open[1] != opposite(&open[0]) {
+                        turns += 1;
+                    }
+                }
+                3 | 4 => junctions += 1,
+                _ => {}
+            }
+            path_cells.push(c);
+        }
+    }
+    /* each passage opens two cells, a tree has one less than its cells */
+    let is_perfect = nb_openings / 2 + 1 == cells;
+    let diameter = diameter(maze, &path_cells, is_perfect);
+
+    let solution = maze.solution();
+    let solution_length = solution.len().saturating_sub(1);
+    let solution_turns = solution
+        .windows(3)
+        .filter(|w| w[0].x.abs_diff(w[2].x) == 1 && w[0].y.abs_diff(w[2].y) == 1)
+        .count();
+
+    Stats {
+        cells,
+        dead_ends,
+        dead_end_ratio: ratio(dead_ends as f64, cells as f64),
+        junctions,
+        corridors,
+        solution_length,
+        diameter,
+        solution_ratio: ratio(solution_length as f64, diameter as f64),
+        average_branch_length: ratio(branches as f64, dead_ends as f64),
+        river_factor: ratio(corridors as f64, cells as f64),
+        turns,
+        solution_turns,
+    }
+}
+
+impl Stats {
+    /// Statistics as a JSON object.
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"cells\":{},\"dead_ends\":{},\"dead_end_ratio\":{},\"junctions\":{},\
+             \"corridors\":{},\"solution_length\":{},\"diameter\":{},\
+             \"solution_ratio\":{},\"average_branch_length\":{},\"river_factor\":{},\
+             \"turns\":{},\"solution_turns\":{}}}",
+            self.cells,
+            self.dead_ends,
+            self.dead_end_ratio,
+            self.junctions,
+            self.corridors,
+            self.solution_length,
+            self.diameter,
+            self.solution_ratio,
+            self.average_branch_length,
+            self.river_factor,
+            self.turns,
+            self.solution_turns
+        )
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "cells:                 {}", self.cells)?;
+        writeln!(
+            f,
+            "dead ends:             {} ({:.1}%)",
+            self.dead_ends,
+            100_f64 * self.dead_end_ratio
+        )?;
+        writeln!(f, "junctions:             {}", self.junctions)?;
+        writeln!(f, "corridors:             {}", self.corridors)?;
+        writeln!(
+            f,
+            "solution length:       {} ({:.1}% of the diameter, {})",
+            self.solution_length,
+            100_f64 * self.solution_ratio,
+            self.diameter
+        )?;
+        writeln!(
+            f,
+            "average branch length: {:.2}",
+            self.average_branch_length
+        )?;
+        writeln!(f, "river factor:          {:.3}", self.river_factor)?;
+        writeln!(f, "turns:                 {}", self.turns)?;
+        writeln!(f, "solution turns:        {}", self.solution_turns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AlgorithmKind, Geometry, MazeBuilder, Origin};
+
+    fn maze(braid: f64) -> Maze {
+        MazeBuilder::new()
+            .geometry(Geometry {
+                width: 124,
+                height: 84,
+            })
+            .algorithm(AlgorithmKind::Prim)
+            .origin(Origin { x: 0.5, y: 0.5 })
+            .braid(braid)
+            .seed(8)
+            .build()
+    }
+
+    /* Brute force diameter, trying every cell */
+    fn max_eccentricity(maze: &Maze) -> usize {
+        let g = maze.geometry();
+        let cells: Vec<Coord> = (0..g.height)
+            .step_by(2)
+            .flat_map(|y| (0..g.width).step_by(2).map(move |x| Coord { x, y }))
+            .collect();
+        diameter(maze, &cells, false)
+    }
+
+    #[test]
+    fn diameter_of_perfect_maze() {
+        let maze = maze(0.0);
+        let s = stats(&maze);
+        assert_eq!(s.diameter, max_eccentricity(&maze));
+        /* from the middle, the end is not at an end of the longest path */
+        assert!(s.solution_ratio < 1.0);
+        assert_eq!(s.solution_length as f64, maze.len());
+    }
+
+    #[test]
+    fn diameter_of_braided_maze() {
+        let maze = maze(0.5);
+        let s = stats(&maze);
+        assert_eq!(s.diameter, max_eccentricity(&maze));
+        assert!(s.solution_length <= s.diameter);
+    }
+}
@@ -0,0 +1,143 @@
+use crate::maze::{Coord, Maze, Rendering};
+use crate::Layout;
+use image::Rgb;
+use std::fmt::Write;
+
+fn hex(color: &Rgb<u8>) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn write_rect<T: ?Sized + Rendering>(
+    svg: &mut String,
+    renderer: &T,
+    x: (usize, usize),
+    y: usize,
+    color: &Rgb<u8>,
+) {
+    writeln!(
+        svg,
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>",
+        renderer.tile_offset(x.0),
+        renderer.tile_offset(y),
+        renderer.tile_offset(x.1) - renderer.tile_offset(x.0),
+        renderer.tile_offset(y + 1) - renderer.tile_offset(y),
+        hex(color)
+    )
+    .unwrap();
+}
+
+/* Vector rendering of the maze: a background filled with the wall color and,
+ * on each row, a rectangle per run of cells sharing the same color. With a
+ * gradient, each run gets its own fill. The maze is placed in the image by
+ * shifting the view box. The cells of `overlay` are drawn last, with their
+ * flat color. */
+pub fn draw_svg<T: ?Sized + Rendering>(
+    maze: &Maze,
+    renderer: &T,
+    layout: &Layout,
+    overlay: &[(Vec<Coord>, Rgb<u8>)],
+) -> String {
+    let g = maze.geometry();
+    let background = renderer.cell_color(&crate::CellKind::WallKind);
+
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"-{x} -{y} {w} {h}\" shape-rendering=\"crispEdges\">",
+        x = layout.left,
+        y = layout.top,
+        w = layout.geometry.width,
+        h = layout.geometry.height
+    )
+    .unwrap();
+    writeln!(
+        svg,
+        "<rect x=\"-{}\" y=\"-{}\" width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+        layout.left,
+        layout.top,
+        hex(&background)
+    )
+    .unwrap();
+
+    for y in 0..g.height {
+        let mut x = 0;
+        while x < g.width {
+            let color = renderer.cell_color(&maze.cell_kind(&Coord { x, y }));
+            let start = x;
+            x += 1;
+            while x < g.width && renderer.cell_color(&maze.cell_kind(&Coord { x, y })) == color {
+                x += 1;
+            }
+            if color == background {
+                continue;
+            }
+            write_rect(&mut svg, renderer, (start, x), y, &color);
+        }
+    }
+    for (cells, color) in overlay {
+        for c in cells {
+            write_rect(&mut svg, renderer, (c.x, c.x + 1), c.y, color);
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plain::RendererPlain;
+    use crate::{AlgorithmKind, CellKind, Geometry, MazeBuilder};
+
+    #[test]
+    fn rects_and_view_box() {
+        let renderer = RendererPlain {
+            path_color_start: Rgb([255, 255, 255]),
+            path_color_end: Rgb([255, 255, 255]),
+            wall_color: Rgb([0, 0, 0]),
+            tile_size: 3,
+        };
+        let maze = MazeBuilder::new()
+            .geometry(Geometry {
+                width: 90,
+                height: 60,
+            })
+            .algorithm(AlgorithmKind::Backtracker)
+            .seed(5)
+            .build();
+        let layout = Layout {
+            geometry: Geometry {
+                width: 130,
+                height: 80,
+            },
+            left: 7,
+            top: 5,
+        };
+        let svg = draw_svg(&maze, &renderer, &layout, &[]);
+        assert!(svg.starts_with(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"130\" height=\"80\" \
+             viewBox=\"-7 -5 130 80\""
+        ));
+
+        /* the background, then one rectangle per run of paths on each row */
+        let g = maze.geometry();
+        let mut runs = 0;
+        for y in 0..g.height {
+            for x in 0..g.width {
+                let is_path = |x| matches!(maze.cell_kind(&Coord { x, y }), CellKind::PathKind(_));
+                if is_path(x) && (x == 0 || !is_path(x - 1)) {
+                    runs += 1;
+                }
+            }
+        }
+        assert_eq!(svg.matches("<rect ").count(), 1 + runs);
+
+        let overlay = [(maze.solution(), Rgb([255, 0, 0]))];
+        let svg = draw_svg(&maze, &renderer, &layout, &overlay);
+        assert_eq!(
+            svg.matches("fill=\"#ff0000\"").count(),
+            maze.solution().len()
+        );
+    }
+}
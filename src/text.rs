@@ -0,0 +1,79 @@
+use crate::maze::{CellKind, Coord, Maze, Rendering};
+use image::Rgb;
+use std::fmt::Write;
+
+fn is_wall(cell_kind: &CellKind) -> bool {
+    !matches!(cell_kind, CellKind::PathKind(_))
+}
+
+/* Text rendering of the maze with half-block glyphs: each character shows
+ * two cells stacked vertically. Without colors, walls are drawn as blocks
+ * and paths are left blank. With colors, every character is an upper half
+ * block whose foreground is the top cell and background the bottom cell,
+ * using 24-bit ANSI escape codes. */
+pub fn draw_text<T: ?Sized + Rendering>(maze: &Maze, renderer: &T, ansi: bool) -> String {
+    let g = maze.geometry();
+    let mut text = String::new();
+    for y in (0..g.height).step_by(2) {
+        let mut colors: Option<(Rgb<u8>, Option<Rgb<u8>>)> = None;
+        for x in 0..g.width {
+            let top = maze.cell_kind(&Coord { x, y });
+            let bottom = if y + 1 < g.height {
+                Some(maze.cell_kind(&Coord { x, y: y + 1 }))
+            } else {
+                None
+            };
+            if !ansi {
+                let glyph = match (is_wall(&top), bottom.as_ref().map(is_wall)) {
+                    (true, Some(true)) => '█',
+                    (true, _) => '▀',
+                    (false, Some(true)) => '▄',
+                    (false, _) => ' ',
+                };
+                text.push(glyph);
+                continue;
+            }
+            let fg = renderer.cell_color(&top);
+            let bg = bottom.map(|b| renderer.cell_color(&b));
+            if colors != Some((fg, bg)) {
+                write!(text, "\x1b[38;2;{};{};{}m", fg[0], fg[1], fg[2]).unwrap();
+                match bg {
+                    Some(bg) => write!(text, "\x1b[48;2;{};{};{}m", bg[0], bg[1], bg[2]).unwrap(),
+                    None => text.push_str("\x1b[49m"),
+                }
+                colors = Some((fg, bg));
+            }
+            text.push('▀');
+        }
+        if ansi {
+            text.push_str("\x1b[0m");
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Geometry, MazeBuilder};
+
+    #[test]
+    fn lines() {
+        let builder = MazeBuilder::new()
+            .geometry(Geometry {
+                width: 40,
+                height: 20,
+            })
+            .seed(3);
+        let maze = builder.build_text();
+        assert_eq!((maze.geometry().width, maze.geometry().height), (39, 19));
+        let text = builder.draw_text(&maze);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 10);
+        for line in &lines {
+            assert_eq!(line.chars().count(), 39);
+        }
+        /* the last line only shows the top halves of its characters */
+        assert!(lines[9].chars().all(|c| c == '▀' || c == ' '));
+    }
+}